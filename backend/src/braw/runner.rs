@@ -138,12 +138,18 @@ fn build_braw_ffmpeg_args(
         .as_deref()
         .map(|r| r.replace('x', ":"));
     // BRAW: kein HW-Accel (rawvideo von Pipe), kein NVDEC
+    // HDR-Erkennung (`ffmpeg::probe::probe_media_metadata`) laeuft nicht ueber die
+    // BRAW-Bridge – Default-Farbmetadaten (kein Tonemap, keine Tags) bis die
+    // Bridge eigene Farbraum-Infos liefert.
     push_proxy_codec_args(
         &mut args,
         &options.proxy_codec,
         "none",
         resolution.as_deref(),
         false,
+        None,
+        None, // Rate-Control-Modi werden ueber die BRAW-Bridge nicht unterstuetzt
+        &crate::ffmpeg::runner::ColorMetadata::default(),
     );
 
     // Audio: kein Audio bei BRAW-Proxies (BRAW enthaelt kein Audio)
@@ -173,6 +179,7 @@ pub async fn run_braw_job(
     tx: mpsc::Sender<FfmpegEvent>,
     cancel: CancellationToken,
     pid_slot: Arc<AtomicU32>,
+    ffmpeg_pid_slot: Arc<AtomicU32>,
 ) -> Result<()> {
     let bridge = find_braw_bridge();
     let ffmpeg_args = build_braw_ffmpeg_args(&output_path, options, &meta);
@@ -229,8 +236,23 @@ pub async fn run_braw_job(
         .spawn()
         .context("FFmpeg konnte nicht gestartet werden")?;
 
+    // PID von FFmpeg speichern (fuer Pause/Resume SIGSTOP/SIGCONT) – braw-bridge
+    // und FFmpeg sind zwei getrennte Prozesse, beide muessen signalisiert werden.
+    ffmpeg_pid_slot.store(ffmpeg_child.id().unwrap_or(0), Ordering::Release);
+
     let total_frames = meta.frame_count;
 
+    // Stall-Watchdog: wird bei jeder NDJSON-Progress-Zeile mit gestiegener `frame`-Zahl
+    // zurueckgesetzt. `0` = deaktiviert.
+    let watchdog_duration = if options.process_timeout_secs > 0 {
+        tokio::time::Duration::from_secs(options.process_timeout_secs)
+    } else {
+        tokio::time::Duration::from_secs(u64::MAX / 2)
+    };
+    let watchdog = tokio::time::sleep(watchdog_duration);
+    tokio::pin!(watchdog);
+    let mut last_frame: u64 = 0;
+
     // Event-Loop: braw-bridge stderr lesen fuer Progress, Cancel abfangen
     loop {
         tokio::select! {
@@ -243,6 +265,7 @@ pub async fn run_braw_job(
                 let _ = bridge_child.wait().await;
                 let _ = ffmpeg_child.wait().await;
                 pid_slot.store(0, Ordering::Release);
+                ffmpeg_pid_slot.store(0, Ordering::Release);
                 let _ = tx
                     .send(FfmpegEvent::Cancelled {
                         id: job_id.clone(),
@@ -250,12 +273,36 @@ pub async fn run_braw_job(
                     .await;
                 return Ok(());
             }
+            () = &mut watchdog, if options.process_timeout_secs > 0 => {
+                // Kein Fortschritt innerhalb von `process_timeout_secs` – Pipeline haengt.
+                let bridge_pid = pid_slot.load(Ordering::Acquire);
+                if bridge_pid != 0 {
+                    unsafe { libc::kill(bridge_pid as libc::pid_t, libc::SIGTERM); }
+                }
+                let _ = bridge_child.wait().await;
+                let _ = ffmpeg_child.kill().await;
+                let _ = ffmpeg_child.wait().await;
+                pid_slot.store(0, Ordering::Release);
+                ffmpeg_pid_slot.store(0, Ordering::Release);
+                let _ = tx
+                    .send(FfmpegEvent::Error {
+                        id: job_id.clone(),
+                        message: "timeout".to_string(),
+                    })
+                    .await;
+                return Ok(());
+            }
             line = stderr_reader.next_line() => {
                 match line {
                     Ok(Some(line)) => {
                         // Progress-Events parsen: {"type":"progress","frame":42,"total":1200}
                         if let Ok(v) = serde_json::from_str::<serde_json::Value>(&line) {
                             if v["type"].as_str() == Some("progress") {
+                                let advanced_frame = v["frame"].as_u64().unwrap_or(0);
+                                if advanced_frame > last_frame {
+                                    last_frame = advanced_frame;
+                                    watchdog.as_mut().reset(tokio::time::Instant::now() + watchdog_duration);
+                                }
                                 let frame = v["frame"].as_u64().unwrap_or(0);
                                 let percent = if total_frames > 0 {
                                     (frame as f32 / total_frames as f32 * 100.0).clamp(0.0, 100.0)
@@ -269,6 +316,7 @@ pub async fn run_braw_job(
                                         fps: 0.0,
                                         speed: 0.0,
                                         frame,
+                                        elapsed_us: 0, // braw-bridge trackt Frames, keine out_time_us
                                     })
                                     .await;
                             }
@@ -279,6 +327,7 @@ pub async fn run_braw_job(
                         let bridge_status = bridge_child.wait().await?;
                         let ffmpeg_status = ffmpeg_child.wait().await?;
                         pid_slot.store(0, Ordering::Release);
+                        ffmpeg_pid_slot.store(0, Ordering::Release);
 
                         if !bridge_status.success() {
                             let _ = tx
@@ -314,6 +363,7 @@ pub async fn run_braw_job(
                         let _ = ffmpeg_child.kill().await;
                         let _ = ffmpeg_child.wait().await;
                         pid_slot.store(0, Ordering::Release);
+                        ffmpeg_pid_slot.store(0, Ordering::Release);
                         let _ = tx
                             .send(FfmpegEvent::Error {
                                 id: job_id.clone(),
@@ -0,0 +1,543 @@
+// Chunked-Encoding: Teilt einen grossen Input an Keyframe-Grenzen in mehrere
+// unabhaengig kodierbare Segmente auf, kodiert sie parallel und fuegt sie per
+// Concat-Demuxer verlustfrei wieder zusammen. Saettigt Multi-Core-Maschinen,
+// statt nur einen seriellen FFmpeg-Prozess laufen zu lassen.
+//
+// Kritische Invariante: alle Segmente muessen mit identischen Codec/Pixfmt-
+// Parametern kodiert werden (dieselbe `push_proxy_codec_args`-Logik wie der
+// normale Pfad), sonst schlaegt der abschliessende `-c copy`-Concat fehl oder
+// ruckelt an den Nahtstellen. Ausserdem duerfen Segmentgrenzen nur auf echten
+// Keyframes liegen, damit jedes Segment unabhaengig dekodierbar ist.
+//
+// Zwei Erkennungsstrategien fuer Segmentgrenzen stehen bereit: `select='gt(scene,T)'`
+// + `showinfo` (`probe_scene_cut_timestamps`, inhaltsbasiert) sowie reines Abtasten
+// der Keyframe-Zeitstempel (`probe_keyframe_timestamps`, fuer gleichmaessige
+// Aufteilung ohne Szenenerkennung); `probe_scene_chunks` kombiniert beide, indem
+// Szenenschnitte auf den naechsten echten Keyframe abgerundet werden.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use crate::ffmpeg::progress::ProgressParser;
+use crate::ffmpeg::runner::{push_proxy_codec_args, FfmpegEvent};
+use crate::ipc::protocol::{JobMode, JobOptions};
+use crate::jobs::transcode::PidSet;
+
+/// Start/Ende eines Segments in Mikrosekunden, jeweils an einem Keyframe ausgerichtet.
+#[derive(Debug, Clone, Copy)]
+struct ChunkBounds {
+    start_us: i64,
+    end_us: i64,
+}
+
+/// Ermittelt alle Keyframe-Zeitstempel der Quelle via ffprobe (aufsteigend, mit `0`
+/// als erstem Eintrag). Grundlage sowohl fuer die gleichmaessige Keyframe-Aufteilung
+/// (`probe_keyframe_chunks`) als auch fuer das Abrunden von Szenenschnitten auf
+/// echte Keyframes (`probe_scene_chunks`).
+async fn probe_keyframe_timestamps(input_path: &Path) -> Result<Vec<i64>> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-skip_frame",
+            "nokey",
+            "-show_entries",
+            "frame=pts_time",
+            "-of",
+            "csv=print_section=0",
+        ])
+        .arg(input_path.as_os_str())
+        .output()
+        .await
+        .context("ffprobe (Keyframe-Liste) konnte nicht gestartet werden")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe (Keyframe-Liste) beendet mit Exit-Code: {}",
+            output.status.code().unwrap_or(-1)
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut keyframes_us: Vec<i64> = stdout
+        .lines()
+        .filter_map(|l| l.trim().parse::<f64>().ok())
+        .map(|secs| (secs * 1_000_000.0) as i64)
+        .collect();
+    keyframes_us.sort_unstable();
+    keyframes_us.dedup();
+
+    if keyframes_us.first() != Some(&0) {
+        keyframes_us.insert(0, 0);
+    }
+    Ok(keyframes_us)
+}
+
+/// Gruppiert die Keyframes greedy in ungefaehr `target_chunks` gleich lange Segmente.
+/// Es wird ausschliesslich an Keyframes geteilt (nie mitten in einer GOP).
+async fn probe_keyframe_chunks(
+    input_path: &Path,
+    total_duration_us: i64,
+    target_chunks: usize,
+) -> Result<Vec<ChunkBounds>> {
+    let keyframes_us = probe_keyframe_timestamps(input_path).await?;
+
+    let target_chunks = target_chunks.max(1);
+    let target_len_us = (total_duration_us / target_chunks as i64).max(1);
+
+    let mut bounds = Vec::new();
+    let mut group_start = keyframes_us[0];
+    for &kf in &keyframes_us[1..] {
+        if kf - group_start >= target_len_us && bounds.len() + 1 < target_chunks {
+            bounds.push(ChunkBounds {
+                start_us: group_start,
+                end_us: kf,
+            });
+            group_start = kf;
+        }
+    }
+    bounds.push(ChunkBounds {
+        start_us: group_start,
+        end_us: total_duration_us,
+    });
+    Ok(bounds)
+}
+
+/// Erkennt Szenenwechsel via FFmpegs `select='gt(scene,THRESH)'`-Filter + `showinfo`
+/// und liefert deren Zeitstempel (Mikrosekunden, aufsteigend, dedupliziert).
+async fn probe_scene_cut_timestamps(input_path: &Path, scene_threshold: f32) -> Result<Vec<i64>> {
+    let filter = format!("select='gt(scene,{scene_threshold})',showinfo");
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(["-v", "info", "-i"])
+        .arg(input_path.as_os_str())
+        .args(["-vf", &filter, "-f", "null", "-"])
+        .output()
+        .await
+        .context("FFmpeg (Szenenerkennung) konnte nicht gestartet werden")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "FFmpeg (Szenenerkennung) beendet mit Exit-Code: {}",
+            output.status.code().unwrap_or(-1)
+        ));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut cuts_us: Vec<i64> = stderr
+        .lines()
+        .filter_map(|line| line.find("pts_time:").map(|idx| &line[idx + "pts_time:".len()..]))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .filter_map(|s| s.parse::<f64>().ok())
+        .map(|secs| (secs * 1_000_000.0) as i64)
+        .collect();
+    cuts_us.sort_unstable();
+    cuts_us.dedup();
+    Ok(cuts_us)
+}
+
+/// Teilt `[start_us, end_us)` an den darin liegenden Keyframes weiter auf: geteilt
+/// wird am jeweils ersten Keyframe, der mindestens `max_len_us` hinter dem Start des
+/// laufenden Teilsegments liegt. Ein Teilsegment kann daher um bis zu einen
+/// Keyframe-Abstand laenger als `max_len_us` sein. Wird genutzt um lange (z.B.
+/// statische) Szenen trotzdem fuer Parallelitaet aufzuteilen.
+fn subdivide_at_keyframes(keyframes_us: &[i64], start_us: i64, end_us: i64, max_len_us: i64) -> Vec<ChunkBounds> {
+    let mut bounds = Vec::new();
+    let mut group_start = start_us;
+    for &kf in keyframes_us.iter().filter(|&&k| k > start_us && k < end_us) {
+        if kf - group_start >= max_len_us {
+            bounds.push(ChunkBounds { start_us: group_start, end_us: kf });
+            group_start = kf;
+        }
+    }
+    bounds.push(ChunkBounds { start_us: group_start, end_us: end_us });
+    bounds
+}
+
+/// Ermittelt Segmentgrenzen per Szenenerkennung statt gleichmaessiger Keyframe-
+/// Aufteilung: jeder erkannte Schnitt wird auf den vorangehenden echten Keyframe
+/// abgerundet (Invariante aus `build_chunk_args` – Segmentgrenzen muessen auf
+/// Keyframes liegen), danach wird jedes resultierende Segment, das `max_scene_secs`
+/// ueberschreitet (z.B. eine lange statische Szene), an weiteren Keyframes
+/// gleichmaessig nachunterteilt.
+async fn probe_scene_chunks(
+    input_path: &Path,
+    total_duration_us: i64,
+    scene_threshold: f32,
+    max_scene_secs: f64,
+) -> Result<Vec<ChunkBounds>> {
+    let keyframes_us = probe_keyframe_timestamps(input_path).await?;
+    let scene_cuts_us = probe_scene_cut_timestamps(input_path, scene_threshold).await?;
+
+    let mut boundaries: Vec<i64> = vec![0];
+    for cut in scene_cuts_us {
+        if let Some(&kf) = keyframes_us.iter().rev().find(|&&k| k <= cut) {
+            boundaries.push(kf);
+        }
+    }
+    boundaries.push(total_duration_us);
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let max_len_us = (max_scene_secs.max(0.0) * 1_000_000.0) as i64;
+    let mut bounds = Vec::new();
+    for pair in boundaries.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        if max_len_us > 0 && end - start > max_len_us {
+            bounds.extend(subdivide_at_keyframes(&keyframes_us, start, end, max_len_us));
+        } else {
+            bounds.push(ChunkBounds { start_us: start, end_us: end });
+        }
+    }
+    Ok(bounds)
+}
+
+/// Ermittelt Gesamt-Framezahl und Framerate via `probe_media_metadata`. Die
+/// Framezahl dient der Progress-Gewichtung (0 wenn `nb_frames` nicht im
+/// Container steht – Progress basiert dann nur auf Chunk-Anzahl statt
+/// Frame-Summe), die Framerate dem Timecode-Offset pro Segment (siehe
+/// `offset_timecode`).
+async fn probe_frame_count_and_fps(input_path: &Path) -> (u64, f32) {
+    crate::ffmpeg::probe::probe_media_metadata(input_path)
+        .await
+        .map(|meta| (meta.nb_frames, meta.fps))
+        .unwrap_or((0, 0.0))
+}
+
+/// Liest den Start-Timecode der Quelle aus `format_tags:timecode` bzw.
+/// `stream_tags:timecode` (SMPTE `HH:MM:SS:FF`/Drop-Frame `HH:MM:SS;FF`), falls
+/// vorhanden. Ohne Timecode-Tag bleibt `-timecode` pro Segment einfach weg
+/// (kein Pflichtfeld fuer die meisten Container) statt mit `00:00:00:00` zu raten.
+async fn probe_start_timecode(input_path: &Path) -> Option<String> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format_tags=timecode:stream_tags=timecode",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+        ])
+        .arg(input_path.as_os_str())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+/// Addiert `offset_us` (auf ganze Frames bei `fps` gerundet) auf einen
+/// Start-Timecode (`HH:MM:SS:FF`/Drop-Frame `HH:MM:SS;FF`) – jedes Chunk-Segment
+/// startet sonst faelschlich wieder bei der Quell-Timecode, obwohl es inhaltlich
+/// mitten im Clip liegt. `None` wenn `base` sich nicht parsen laesst oder `fps`
+/// ungueltig ist; der Aufrufer laesst `-timecode` dann weg statt falsche Werte
+/// zu raten.
+fn offset_timecode(base: &str, fps: f32, offset_us: i64) -> Option<String> {
+    if fps <= 0.0 {
+        return None;
+    }
+    let drop_frame = base.contains(';');
+    let parts: Vec<&str> = base.split(|c| c == ':' || c == ';').collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    let h: i64 = parts[0].parse().ok()?;
+    let m: i64 = parts[1].parse().ok()?;
+    let s: i64 = parts[2].parse().ok()?;
+    let f: i64 = parts[3].parse().ok()?;
+
+    let fps_rounded = fps.round() as i64;
+    if fps_rounded <= 0 {
+        return None;
+    }
+    let base_frames = (h * 3600 + m * 60 + s) * fps_rounded + f;
+    let offset_frames = (offset_us as f64 / 1_000_000.0 * fps as f64).round() as i64;
+    let total_frames = base_frames + offset_frames;
+
+    let total_secs = total_frames / fps_rounded;
+    let ff = total_frames % fps_rounded;
+    let hh = total_secs / 3600;
+    let mm = (total_secs % 3600) / 60;
+    let ss = total_secs % 60;
+    let sep = if drop_frame { ';' } else { ':' };
+    Some(format!("{hh:02}:{mm:02}:{ss:02}{sep}{ff:02}"))
+}
+
+/// Baut die FFmpeg-Argumente fuer ein einzelnes Segment: identische Codec-Parameter
+/// wie der normale Proxy-Pfad, zeitlich begrenzt auf `[start_us, end_us)`.
+fn build_chunk_args(
+    input_path: &Path,
+    segment_path: &Path,
+    options: &JobOptions,
+    bounds: ChunkBounds,
+    timecode: Option<&str>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    args.push("-y".to_string());
+    args.push("-loglevel".to_string());
+    args.push("warning".to_string());
+
+    // Input-seitiges Seek (schnell) auf den Start-Keyframe
+    args.push("-ss".to_string());
+    args.push(format!("{:.6}", bounds.start_us as f64 / 1_000_000.0));
+    args.push("-to".to_string());
+    args.push(format!("{:.6}", bounds.end_us as f64 / 1_000_000.0));
+    args.push("-i".to_string());
+    args.push(input_path.to_string_lossy().to_string());
+
+    args.push("-map".to_string());
+    args.push("0:v:0".to_string());
+    args.push("-map".to_string());
+    args.push("0:a?".to_string());
+
+    // Globale Metadaten uebernehmen (wie der normale Proxy-Pfad, siehe
+    // `build_ffmpeg_args`), darueber hinaus den auf den Segmentstart versetzten
+    // Timecode setzen (siehe `offset_timecode`) – sonst traegt jedes Segment
+    // faelschlich den Timecode des Clip-Anfangs statt seiner tatsaechlichen Position.
+    args.push("-map_metadata".to_string());
+    args.push("0".to_string());
+    if let Some(tc) = timecode {
+        args.push("-timecode".to_string());
+        args.push(tc.to_string());
+    }
+
+    let resolution = options.proxy_resolution.as_deref().map(|r| r.replace('x', ":"));
+    // Target-Quality (`JobOptions::target_vmaf`) wird fuer Chunked-Encodes nicht
+    // unterstuetzt – jedes Segment muesste sonst einzeln konvergieren, was die
+    // Invariante identischer Codec-Parameter pro Segment verkomplizieren wuerde.
+    // Aus demselben Grund: HDR-Tagging/Tonemap wird hier nicht aufgeloest, da
+    // jedes Segment dieselben Farbmetadaten-Argumente tragen muss – Default-Wert
+    // (kein Tonemap, keine Tags) bis Chunked-Jobs die Quell-Farbraum-Info erhalten.
+    push_proxy_codec_args(&mut args, &options.proxy_codec, &options.hw_accel, resolution.as_deref(), false, None, options.rate_control.as_ref(), &crate::ffmpeg::runner::ColorMetadata::default());
+
+    args.push("-c:a".to_string());
+    args.push("pcm_s16le".to_string());
+
+    args.push("-progress".to_string());
+    args.push("pipe:2".to_string());
+    args.push(segment_path.to_string_lossy().to_string());
+    args
+}
+
+/// Kodiert ein einzelnes Segment und gibt die erreichte Framezahl zurueck (der
+/// Aufrufer traegt sie in `frames_done` ein).
+async fn encode_chunk(
+    input_path: PathBuf,
+    segment_path: PathBuf,
+    options: JobOptions,
+    bounds: ChunkBounds,
+    timecode: Option<String>,
+    pid_slot: Arc<AtomicU32>,
+) -> Result<u64> {
+    let args = build_chunk_args(&input_path, &segment_path, &options, bounds, timecode.as_deref());
+
+    let mut child = tokio::process::Command::new("ffmpeg")
+        .args(&args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("FFmpeg (Chunk) konnte nicht gestartet werden")?;
+
+    pid_slot.store(child.id().unwrap_or(0), Ordering::Release);
+
+    let stderr = child.stderr.take().context("Konnte stderr des Chunk-Prozesses nicht lesen")?;
+    let mut reader = BufReader::new(stderr).lines();
+    let mut parser = ProgressParser::new();
+    let mut last_frame = 0u64;
+
+    while let Ok(Some(line)) = reader.next_line().await {
+        if let Some(progress) = parser.feed_line(&line) {
+            last_frame = progress.frame;
+            if progress.is_done {
+                break;
+            }
+        }
+    }
+
+    let status = child.wait().await.context("Warten auf Chunk-Prozess fehlgeschlagen")?;
+    pid_slot.store(0, Ordering::Release);
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Chunk-FFmpeg beendet mit Exit-Code: {}",
+            status.code().unwrap_or(-1)
+        ));
+    }
+    Ok(last_frame)
+}
+
+/// Fuehrt einen Chunked-Encode durch: Keyframe-Grenzen ermitteln, Segmente mit
+/// begrenzter Parallelitaet kodieren (Default: `std::thread::available_parallelism()`),
+/// und die fertigen Segmente per Concat-Demuxer verlustfrei zusammenfuegen.
+pub async fn run_chunked_job(
+    job_id: String,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    _mode: JobMode,
+    options: JobOptions,
+    total_duration_us: i64,
+    tx: mpsc::Sender<FfmpegEvent>,
+    cancel: CancellationToken,
+    pids: PidSet,
+) -> Result<()> {
+    let available_parallelism = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let chunks = if options.scene_detect {
+        probe_scene_chunks(
+            &input_path,
+            total_duration_us,
+            options.scene_detect_threshold,
+            options.max_scene_secs,
+        )
+        .await?
+    } else {
+        probe_keyframe_chunks(&input_path, total_duration_us, available_parallelism).await?
+    };
+    let (total_frames, fps) = probe_frame_count_and_fps(&input_path).await;
+    let start_timecode = probe_start_timecode(&input_path).await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("proxy-gen-chunks-{job_id}"));
+    tokio::fs::create_dir_all(&tmp_dir)
+        .await
+        .context("Chunk-Tempverzeichnis konnte nicht angelegt werden")?;
+
+    let ext = if options.proxy_codec == "av1" { "mp4" } else { "mov" };
+    // Konkurrenz richtet sich nach CPU-Kernen, nicht nach Chunk-Anzahl – bei
+    // Szenenerkennung ist die Anzahl der Segmente inhaltsgetrieben und kann stark
+    // von `available_parallelism` abweichen.
+    let semaphore = Arc::new(Semaphore::new(available_parallelism.max(1)));
+    let frames_done: Arc<Vec<AtomicU64>> = Arc::new(chunks.iter().map(|_| AtomicU64::new(0)).collect());
+    let chunks_finished = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let chunk_count = chunks.len();
+
+    let mut segment_paths = Vec::with_capacity(chunks.len());
+    let mut handles = Vec::with_capacity(chunks.len());
+
+    for (idx, bounds) in chunks.iter().copied().enumerate() {
+        let segment_path = tmp_dir.join(format!("chunk-{idx:04}.{ext}"));
+        segment_paths.push(segment_path.clone());
+
+        let semaphore = semaphore.clone();
+        let input_path = input_path.clone();
+        let options = options.clone();
+        let pid_slot = pids.register().await;
+        let frames_done = frames_done.clone();
+        let chunks_finished = chunks_finished.clone();
+        let tx = tx.clone();
+        let job_id = job_id.clone();
+        let timecode = start_timecode
+            .as_deref()
+            .and_then(|tc| offset_timecode(tc, fps, bounds.start_us));
+
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("Semaphore wurde geschlossen");
+            let result = encode_chunk(input_path, segment_path, options, bounds, timecode, pid_slot).await;
+            if let Ok(frame) = result {
+                frames_done[idx].store(frame, Ordering::Release);
+                let finished = chunks_finished.fetch_add(1, Ordering::AcqRel) + 1;
+                let done: u64 = frames_done.iter().map(|f| f.load(Ordering::Acquire)).sum();
+                let percent = if total_frames > 0 {
+                    (done as f32 / total_frames as f32 * 100.0).clamp(0.0, 100.0)
+                } else {
+                    (finished as f32 / chunk_count.max(1) as f32 * 100.0).clamp(0.0, 100.0)
+                };
+                let _ = tx
+                    .send(FfmpegEvent::Progress {
+                        id: job_id,
+                        percent,
+                        fps: 0.0,
+                        speed: 0.0,
+                        frame: done,
+                        elapsed_us: 0, // Chunked-Encode trackt Frames, keine out_time_us
+                    })
+                    .await;
+            }
+            result
+        }));
+    }
+
+    // Auf alle Segmente warten, dabei weiterhin auf Cancel reagieren.
+    let mut failure: Option<anyhow::Error> = None;
+    for handle in handles {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                pids.signal_all(libc::SIGTERM).await;
+                for seg in &segment_paths {
+                    let _ = tokio::fs::remove_file(seg).await;
+                }
+                let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+                let _ = tx.send(FfmpegEvent::Cancelled { id: job_id.clone() }).await;
+                return Ok(());
+            }
+            joined = handle => {
+                match joined {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => failure = Some(e),
+                    Err(e) => failure = Some(anyhow::anyhow!("Chunk-Task Panik: {e}")),
+                }
+            }
+        }
+    }
+
+    if let Some(e) = failure {
+        for seg in &segment_paths {
+            let _ = tokio::fs::remove_file(seg).await;
+        }
+        let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+        let _ = tx
+            .send(FfmpegEvent::Error {
+                id: job_id,
+                message: format!("Chunked-Encode fehlgeschlagen: {e}"),
+            })
+            .await;
+        return Ok(());
+    }
+
+    // Concat-Liste schreiben und verlustfrei zusammenfuegen.
+    let list_path = tmp_dir.join("concat.txt");
+    let list_content: String = segment_paths
+        .iter()
+        .map(|p| format!("file '{}'\n", p.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    tokio::fs::write(&list_path, list_content)
+        .await
+        .context("Concat-Liste konnte nicht geschrieben werden")?;
+
+    let concat_status = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "warning", "-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        .args(["-c", "copy"])
+        .arg(&output_path)
+        .status()
+        .await
+        .context("FFmpeg (Concat) konnte nicht gestartet werden")?;
+
+    let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+
+    if concat_status.success() {
+        let _ = tx.send(FfmpegEvent::Done { id: job_id }).await;
+    } else {
+        let _ = tx
+            .send(FfmpegEvent::Error {
+                id: job_id,
+                message: format!(
+                    "Concat-FFmpeg beendet mit Exit-Code: {}",
+                    concat_status.code().unwrap_or(-1)
+                ),
+            })
+            .await;
+    }
+    Ok(())
+}
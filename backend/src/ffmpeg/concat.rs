@@ -0,0 +1,284 @@
+// Multi-Clip-Concat mit Transitions: verbindet mehrere bereits vorliegende Clips
+// zu einer Timeline per `-filter_complex` (xfade fuer Video, acrossfade fuer
+// Audio) statt per hartem Schnitt (Concat-Demuxer, siehe chunked.rs). Anders als
+// beim Chunked-Encode laeuft hier *ein* FFmpeg-Prozess mit N Inputs.
+//
+// Kritische Invariante: jedes `xfade` ueberlappt zwei Clips um `duration` Sekunden,
+// der Gesamt-Output ist also kuerzer als die Summe aller Clip-Laengen (minus ein
+// Transition-Intervall pro Schnittstelle). Der `offset` des n-ten xfade ist daher
+// die kumulierte Laenge der BEREITS VERKETTETEN Kette (nicht die rohe Clip-Summe)
+// minus die Transition-Dauer. Ausserdem muessen alle Inputs identische Aufloesung
+// und Framerate haben, sonst lehnt xfade sie ab – Clips ab dem zweiten werden bei
+// Abweichung per `scale`/`fps` auf den ersten Clip normalisiert.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::ffmpeg::probe::{probe_media_metadata, MediaMetadata};
+use crate::ffmpeg::progress::{calculate_progress, ProgressParser};
+use crate::ffmpeg::runner::{build_error_message, FfmpegEvent};
+use crate::ipc::protocol::JobOptions;
+
+/// Ordnet den kurzen `JobOptions::transition_kind`-Namen dem tatsaechlichen
+/// `xfade`-Transition-Namen zu. Unbekannte Werte fallen auf "fadeblack" zurueck.
+fn xfade_transition_name(kind: &str) -> &'static str {
+    match kind {
+        "dissolve" => "dissolve",
+        _ => "fadeblack",
+    }
+}
+
+/// Baut die geordnete Clip-Liste: Intro (falls gesetzt) + `input_path` + `concat_clips` + Outro (falls gesetzt).
+fn build_clip_list(input_path: &Path, options: &JobOptions) -> Vec<PathBuf> {
+    let mut clips = Vec::new();
+    if let Some(intro) = &options.intro_clip {
+        clips.push(PathBuf::from(intro));
+    }
+    clips.push(input_path.to_path_buf());
+    clips.extend(options.concat_clips.iter().map(PathBuf::from));
+    if let Some(outro) = &options.outro_clip {
+        clips.push(PathBuf::from(outro));
+    }
+    clips
+}
+
+/// Baut den `-filter_complex`-Graphen fuer alle Clips und gibt ihn zusammen mit den
+/// Labels der finalen Video-/Audio-Pads zurueck (fuer `-map`).
+fn build_filter_complex(
+    metas: &[MediaMetadata],
+    transition: &str,
+    transition_secs: f32,
+) -> (String, String, String) {
+    let mut filter = String::new();
+    let target_w = metas[0].width;
+    let target_h = metas[0].height;
+    let target_fps = metas[0].fps;
+
+    // Clips ab dem zweiten bei abweichender Aufloesung/Framerate normalisieren,
+    // sonst lehnt xfade unterschiedlich dimensionierte Inputs ab.
+    let mut video_labels = vec!["0:v".to_string()];
+    for (i, meta) in metas.iter().enumerate().skip(1) {
+        if meta.width != target_w || meta.height != target_h || (meta.fps - target_fps).abs() > 0.01 {
+            let normalized = format!("n{i}v");
+            filter.push_str(&format!(
+                "[{i}:v]scale={target_w}:{target_h},fps={target_fps}[{normalized}];"
+            ));
+            video_labels.push(normalized);
+        } else {
+            video_labels.push(format!("{i}:v"));
+        }
+    }
+
+    let mut video_label = video_labels[0].clone();
+    let mut audio_label = "0:a".to_string();
+    // Kumulierte Laenge der bereits verketteten Kette – NICHT die rohe Summe der
+    // Eingangsclips, da jeder xfade den Output um `transition_secs` kuerzt.
+    let mut chained_secs = metas[0].duration_us as f64 / 1_000_000.0;
+
+    for i in 1..metas.len() {
+        let out_video = format!("v{i}");
+        let out_audio = format!("a{i}");
+        let offset = (chained_secs - transition_secs as f64).max(0.0);
+
+        filter.push_str(&format!(
+            "[{video_label}][{next_video}]xfade=transition={transition}:duration={transition_secs}:offset={offset:.6}[{out_video}];",
+            next_video = video_labels[i],
+        ));
+        filter.push_str(&format!(
+            "[{audio_label}][{i}:a]acrossfade=d={transition_secs}[{out_audio}];"
+        ));
+
+        video_label = out_video;
+        audio_label = out_audio;
+        chained_secs = offset + metas[i].duration_us as f64 / 1_000_000.0;
+    }
+
+    if filter.ends_with(';') {
+        filter.pop();
+    }
+    (filter, video_label, audio_label)
+}
+
+/// Fuehrt einen Multi-Clip-Concat mit Transitions durch: probt jeden Clip per
+/// ffprobe, baut den xfade/acrossfade-Filtergraphen und startet einen einzelnen
+/// FFmpeg-Prozess. Braucht mindestens zwei Clips (`input_path` + mindestens ein
+/// weiterer ueber `concat_clips`/`intro_clip`/`outro_clip`).
+pub async fn run_concat_job(
+    job_id: String,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    options: JobOptions,
+    tx: mpsc::Sender<FfmpegEvent>,
+    cancel: CancellationToken,
+    pid_slot: Arc<AtomicU32>,
+) -> Result<()> {
+    let clips = build_clip_list(&input_path, &options);
+    if clips.len() < 2 {
+        let _ = tx
+            .send(FfmpegEvent::Error {
+                id: job_id,
+                message: "Concat-Job braucht mindestens zwei Clips (input_path + concat_clips/intro_clip/outro_clip)".to_string(),
+            })
+            .await;
+        return Ok(());
+    }
+
+    let mut metas = Vec::with_capacity(clips.len());
+    for clip in &clips {
+        let meta = probe_media_metadata(clip)
+            .await
+            .with_context(|| format!("ffprobe fehlgeschlagen fuer Clip: {}", clip.display()))?;
+        metas.push(meta);
+    }
+
+    let transition = xfade_transition_name(&options.transition_kind);
+    let transition_secs = options.transition_duration_secs.max(0.0);
+    let (filter_complex, video_label, audio_label) =
+        build_filter_complex(&metas, transition, transition_secs);
+
+    // Gesamtdauer fuer calculate_progress: Summe aller Clips minus ein
+    // Transition-Intervall pro Schnittstelle (xfade kuerzt den Output entsprechend).
+    let raw_sum_us: i64 = metas.iter().map(|m| m.duration_us).sum();
+    let overlap_us = (transition_secs as f64 * 1_000_000.0 * (metas.len() - 1) as f64) as i64;
+    let total_duration_us = (raw_sum_us - overlap_us).max(0);
+
+    let mut args: Vec<String> = vec!["-y".to_string(), "-loglevel".to_string(), "warning".to_string()];
+    for clip in &clips {
+        args.push("-i".to_string());
+        args.push(clip.to_string_lossy().to_string());
+    }
+    args.push("-filter_complex".to_string());
+    args.push(filter_complex);
+    args.push("-map".to_string());
+    args.push(format!("[{video_label}]"));
+    args.push("-map".to_string());
+    args.push(format!("[{audio_label}]"));
+    args.push("-c:v".to_string());
+    args.push("libx264".to_string());
+    args.push("-crf".to_string());
+    args.push("18".to_string());
+    args.push("-pix_fmt".to_string());
+    args.push("yuv420p".to_string());
+    args.push("-c:a".to_string());
+    args.push("pcm_s16le".to_string());
+    args.push("-progress".to_string());
+    args.push("pipe:2".to_string());
+    args.push(output_path.to_string_lossy().to_string());
+
+    let mut child = tokio::process::Command::new("ffmpeg")
+        .args(&args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("FFmpeg (Concat-Transition) konnte nicht gestartet werden")?;
+
+    pid_slot.store(child.id().unwrap_or(0), Ordering::Release);
+
+    let stderr = child
+        .stderr
+        .take()
+        .context("Konnte stderr von FFmpeg nicht lesen")?;
+    let mut stdin = child.stdin.take();
+
+    let mut reader = BufReader::new(stderr).lines();
+    let mut parser = ProgressParser::new();
+    // Letzte Zeilen aus FFmpeg-stderr fuer Fehlermeldungen (max. 20)
+    let mut log_tail: Vec<String> = Vec::with_capacity(20);
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                if let Some(ref mut stdin_handle) = stdin {
+                    let _ = stdin_handle.write_all(b"q\n").await;
+                    let _ = stdin_handle.flush().await;
+                }
+                let _ = child.wait().await;
+                pid_slot.store(0, Ordering::Release);
+                let _ = tx
+                    .send(FfmpegEvent::Cancelled { id: job_id.clone() })
+                    .await;
+                return Ok(());
+            }
+            line = reader.next_line() => {
+                match line {
+                    Ok(Some(line)) => {
+                        if let Some(progress) = parser.feed_line(&line) {
+                            if progress.is_done {
+                                let status = child.wait().await?;
+                                pid_slot.store(0, Ordering::Release);
+                                if status.success() {
+                                    let _ = tx.send(FfmpegEvent::Done { id: job_id.clone() }).await;
+                                } else {
+                                    let _ = std::fs::remove_file(&output_path); // partial file cleanup
+                                    let _ = tx
+                                        .send(FfmpegEvent::Error {
+                                            id: job_id.clone(),
+                                            message: build_error_message(
+                                                status.code().unwrap_or(-1),
+                                                &log_tail,
+                                            ),
+                                        })
+                                        .await;
+                                }
+                                return Ok(());
+                            }
+
+                            let percent = calculate_progress(progress.out_time_us, total_duration_us);
+                            let _ = tx
+                                .send(FfmpegEvent::Progress {
+                                    id: job_id.clone(),
+                                    percent: percent * 100.0,
+                                    fps: progress.fps,
+                                    speed: progress.speed,
+                                    frame: progress.frame,
+                                    elapsed_us: progress.out_time_us,
+                                })
+                                .await;
+                        } else {
+                            if log_tail.len() == 20 {
+                                log_tail.remove(0);
+                            }
+                            log_tail.push(line);
+                        }
+                    }
+                    Ok(None) => {
+                        let status = child.wait().await?;
+                        pid_slot.store(0, Ordering::Release);
+                        if status.success() {
+                            let _ = tx.send(FfmpegEvent::Done { id: job_id.clone() }).await;
+                        } else {
+                            let _ = std::fs::remove_file(&output_path); // partial file cleanup
+                            let _ = tx
+                                .send(FfmpegEvent::Error {
+                                    id: job_id.clone(),
+                                    message: build_error_message(status.code().unwrap_or(-1), &log_tail),
+                                })
+                                .await;
+                        }
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        let _ = child.kill().await;
+                        let _ = child.wait().await; // Zombie verhindern
+                        pid_slot.store(0, Ordering::Release);
+                        let _ = std::fs::remove_file(&output_path); // partial file cleanup
+                        let _ = tx
+                            .send(FfmpegEvent::Error {
+                                id: job_id.clone(),
+                                message: format!("Fehler beim Lesen von stderr: {e}"),
+                            })
+                            .await;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// Hardware-Capability-Erkennung: ersetzt die frueher statische Pixelformat-Allowlist
+// fuer den NVENC-Full-GPU-Pfad (siehe `push_nvenc` in `runner.rs`) durch echte
+// Umgebungs-Introspektion. Beim Start wird einmalig abgefragt, welche Hwaccels/
+// Encoder die installierte FFmpeg-Toolchain mitbringt (`-hwaccels`, `-encoders`),
+// und fuer die bekannten Proxy-Codecs per winziger Probe-Transkodierung getestet,
+// ob NVDEC + `scale_cuda` das jeweilige Pixel-Format tatsaechlich auf der GPU
+// dekodieren/skalieren koennen. Ergebnisse werden pro (Pixel-Format, Codec) gecacht;
+// eine zur Startzeit ungesehene Kombination wird beim ersten Bedarf live nachgeprobt
+// statt pauschal auf die Hybrid-Pipeline zurueckzufallen.
+//
+// `resolve_hw_accel` nutzt dieselbe Encoder-Liste (plus eine VAAPI-Device-Probe),
+// um die angeforderte `proxy_codec x hw_accel`-Kombination vor dem eigentlichen
+// Dispatch zu pruefen und bei fehlender Verfuegbarkeit auf Software-Encoding
+// zurueckzufallen, statt den Job erst am crashenden FFmpeg-Prozess scheitern zu
+// lassen (siehe `jobs::transcode::dispatch_job`).
+
+use std::collections::{HashMap, HashSet};
+
+use tokio::sync::RwLock;
+
+/// Pixel-Formate, fuer die beim Start vorab geprobt wird – deckt die bisherige
+/// statische Allowlist sowie die bekannten 4:2:2-Problemfaelle ab (z.B. Sony FX
+/// MXF `p210le`), damit ein unterstuetzender Treiber sie ab dem ersten Job nutzt.
+const KNOWN_PIX_FMTS: &[&str] = &[
+    "yuv420p", "nv12", "yuvj420p",
+    "yuv420p10le", "yuv420p10be",
+    "p010le", "p010be", "p016le",
+    "yuv420p12le", "p012le",
+    "yuv422p10le", "p210le", "p216le",
+];
+
+/// NVENC-Encoder, fuer die der Full-GPU-Pfad relevant ist (siehe `push_nvenc` in
+/// `runner.rs`; AV1-NVENC laeuft immer ueber CPU-Decode, siehe `push_nvenc_av1`).
+const NVENC_CODECS: &[&str] = &["h264_nvenc", "hevc_nvenc"];
+
+/// Gecachte Hwaccel-/Encoder-Verfuegbarkeit plus gelernte (Pixel-Format, Codec) →
+/// Full-GPU-faehig Tabelle. Wird einmal bei Programmstart via `probe()` erzeugt
+/// und danach als `Arc<HwCapabilities>` zwischen Jobs geteilt.
+pub struct HwCapabilities {
+    cuda_hwaccel_available: bool,
+    vaapi_device_available: bool,
+    available_encoders: HashSet<String>,
+    full_gpu: RwLock<HashMap<(String, String), bool>>,
+}
+
+impl HwCapabilities {
+    /// Fragt die Toolchain einmalig ab (`ffmpeg -hwaccels`/`-encoders`) und probiert
+    /// alle `KNOWN_PIX_FMTS` × `NVENC_CODECS`-Kombinationen vor, fuer die ein
+    /// Encoder tatsaechlich vorhanden ist. Laeuft komplett ins Leere (keine CUDA-
+    /// Hwaccel gefunden), ohne eine einzige Probe zu starten.
+    pub async fn probe() -> Self {
+        let cuda_hwaccel_available = list_hwaccels().await.contains("cuda");
+        let vaapi_device_available = find_vaapi_render_node().await;
+        let available_encoders = list_encoders().await;
+
+        let caps = Self {
+            cuda_hwaccel_available,
+            vaapi_device_available,
+            available_encoders,
+            full_gpu: RwLock::new(HashMap::new()),
+        };
+
+        if caps.cuda_hwaccel_available {
+            for &pix_fmt in KNOWN_PIX_FMTS {
+                for &codec in NVENC_CODECS {
+                    if !caps.available_encoders.contains(codec) {
+                        continue;
+                    }
+                    let supported = probe_full_gpu_pipeline(pix_fmt, codec).await;
+                    caps.full_gpu
+                        .write()
+                        .await
+                        .insert((pix_fmt.to_string(), codec.to_string()), supported);
+                }
+            }
+        }
+
+        caps
+    }
+
+    /// Gibt zurueck, ob NVDEC + `scale_cuda` das gegebene Pixel-Format fuer `codec`
+    /// auf dieser Maschine unterstuetzen. Eine beim Start nicht geprobte Kombination
+    /// wird live nachgeprobt und im Cache ergaenzt.
+    pub async fn supports_full_gpu(&self, pix_fmt: &str, codec: &str) -> bool {
+        if !self.cuda_hwaccel_available || !self.available_encoders.contains(codec) {
+            return false;
+        }
+        if let Some(&supported) = self
+            .full_gpu
+            .read()
+            .await
+            .get(&(pix_fmt.to_string(), codec.to_string()))
+        {
+            return supported;
+        }
+        let supported = probe_full_gpu_pipeline(pix_fmt, codec).await;
+        self.full_gpu
+            .write()
+            .await
+            .insert((pix_fmt.to_string(), codec.to_string()), supported);
+        supported
+    }
+
+    /// Preflight fuer `push_proxy_codec_args`: prueft, ob der fuer `proxy_codec x
+    /// hw_accel` benoetigte Encoder (siehe `runner::encoder_name`) auf dieser
+    /// Maschine tatsaechlich vorhanden ist (Encoder-Liste plus, bei VAAPI/NVENC,
+    /// das jeweilige Device). Ist die Kombination nicht nutzbar, wird auf
+    /// Software-Encoding (`hw_accel = "none"`) zurueckgefallen, mit einer
+    /// Nutzer-lesbaren Begruendung fuer `Response::JobHwFallback`. Gibt
+    /// `(hw_accel, None)` zurueck, wenn die angeforderte Kombination unveraendert nutzbar ist
+    /// (inklusive Software-Pfad und ProRes, fuer die `encoder_name` `None` liefert).
+    pub async fn resolve_hw_accel(&self, proxy_codec: &str, hw_accel: &str) -> (String, Option<String>) {
+        let Some(encoder) = crate::ffmpeg::runner::encoder_name(proxy_codec, hw_accel) else {
+            return (hw_accel.to_string(), None);
+        };
+        let device_available = match hw_accel {
+            "vaapi" => self.vaapi_device_available,
+            "nvenc" => self.cuda_hwaccel_available,
+            _ => true,
+        };
+        if device_available && self.available_encoders.contains(encoder) {
+            return (hw_accel.to_string(), None);
+        }
+        (
+            "none".to_string(),
+            Some(format!(
+                "{encoder} auf diesem Host nicht verfuegbar, Job laeuft stattdessen mit Software-Encoding"
+            )),
+        )
+    }
+}
+
+/// Parst `ffmpeg -hwaccels` (eine Kopfzeile, danach ein Name pro Zeile).
+async fn list_hwaccels() -> HashSet<String> {
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(["-hide_banner", "-hwaccels"])
+        .output()
+        .await;
+    match output {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .skip(1)
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Sucht nach einem `/dev/dri/renderD*`-Node (VAAPI-Render-Device). Wird beim
+/// Start einmalig geprobt und gemeinsam mit der Encoder-Liste in
+/// `resolve_hw_accel` herangezogen – ein fehlendes Device crasht sonst erst beim
+/// FFmpeg-Spawn mit `vaapi_device_init`-Fehlern (siehe `retry::FailureClass`).
+async fn find_vaapi_render_node() -> bool {
+    let mut entries = match tokio::fs::read_dir("/dev/dri").await {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_string_lossy().starts_with("renderD") {
+            return true;
+        }
+    }
+    false
+}
+
+/// Parst `ffmpeg -encoders` (Encoder-Name ist das zweite Whitespace-getrennte Feld).
+async fn list_encoders() -> HashSet<String> {
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .await;
+    match output {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(|l| l.split_whitespace().nth(1).map(|s| s.to_string()))
+            .collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Synthetisiert ein einzelnes Frame im gegebenen Pixel-Format und schickt es durch
+/// die volle CUDA-Pipeline (NVDEC-Decode → `scale_cuda` → `codec`-Encode). Erfolg
+/// (Exit-Code 0 bei beiden Schritten) heisst: Full-GPU ist fuer diese Kombination
+/// auf dieser Maschine nutzbar.
+async fn probe_full_gpu_pipeline(pix_fmt: &str, codec: &str) -> bool {
+    let tmp_dir = std::env::temp_dir().join("proxy-gen-hwprobe");
+    if tokio::fs::create_dir_all(&tmp_dir).await.is_err() {
+        return false;
+    }
+    let sample_path = tmp_dir.join(format!("sample-{pix_fmt}.mov"));
+
+    let synth_status = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "quiet"])
+        .args(["-f", "lavfi", "-i", "testsrc=size=64x64:rate=1"])
+        .args(["-frames:v", "1", "-pix_fmt", pix_fmt, "-c:v", "rawvideo"])
+        .arg(&sample_path)
+        .status()
+        .await;
+    if !matches!(synth_status, Ok(s) if s.success()) {
+        let _ = tokio::fs::remove_file(&sample_path).await;
+        return false;
+    }
+
+    let probe_status = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "quiet"])
+        .args(["-hwaccel", "cuda", "-hwaccel_device", "cuda", "-hwaccel_output_format", "cuda"])
+        .arg("-i")
+        .arg(&sample_path)
+        .args(["-vf", "scale_cuda=64:64"])
+        .args(["-c:v", codec, "-frames:v", "1", "-f", "null", "-"])
+        .status()
+        .await;
+
+    let _ = tokio::fs::remove_file(&sample_path).await;
+    matches!(probe_status, Ok(s) if s.success())
+}
@@ -0,0 +1,13 @@
+// FFmpeg-Subsystem: Prozessausfuehrung, Progress-Parsing, Chunked-Encoding,
+// Multi-Clip-Concat mit Transitions und fragmentiertes HLS/fMP4-Streaming.
+
+pub mod chunked;
+pub mod concat;
+pub mod hwcaps;
+pub mod network_sink;
+pub mod probe;
+pub mod progress;
+pub mod retry;
+pub mod runner;
+pub mod segmented;
+pub mod vmaf;
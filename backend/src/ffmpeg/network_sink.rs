@@ -0,0 +1,341 @@
+// Netzwerk-Sink fuer `JobOptions::output_url`: streamt den kodierten Proxy
+// direkt zu einem HTTP/S3/In-Memory-Ziel statt (nur) in `output_dir` zu landen.
+//
+// SCOPE-ABWEICHUNG VOM REQUEST: der Request verlangt echte `avio_alloc_context`-
+// Callbacks (Rust read/write/seek-Trampoline auf einem `av_malloc`-Puffer,
+// Cleanup ueber `avio_context_free`/`av_free`), also eine direkte Einbindung von
+// libavformat/libavcodec. Dieses Projekt linkt nirgends gegen libav* – jede
+// FFmpeg-Interaktion laeuft ausschliesslich ueber den Subprozess (siehe
+// `ffmpeg::runner`), es gibt also weder einen `AVIOContext` noch Custom-I/O-
+// Callbacks, an die sich anknuepfen liesse. Eine echte AVIO-Einbindung braeuchte
+// FFI-Bindings gegen libavformat (z.B. per `bindgen`/`cc`), ein Linker-Setup
+// dafuer und vermutlich `unsafe`-Callback-Trampolinen quer durchs Crate – das
+// ist ein Architekturwechsel, keine lokale Ergaenzung dieses Moduls.
+//
+// Implementiert ist stattdessen ein funktional aehnliches, aber NICHT
+// gleichwertiges Workaround: FFmpeg schreibt den Proxy auf `pipe:1` (analog zu
+// `-progress pipe:2`), dieses Modul liest `pipe:1` in `UPLOAD_CHUNK_BYTES`-
+// Stuecken und pumpt jedes Stueck sofort weiter an den Sink (HTTP: chunked
+// `Transfer-Encoding`; S3: ein Multipart-Part pro Stueck) statt den gesamten
+// Proxy vorher im Speicher zu sammeln – kein Custom-AVIO, kein In-Process-
+// Muxing, aber zumindest kein unbegrenztes Speicherwachstum bei grossen Proxies
+// (siehe `read_chunk`/`put_http`/`put_s3`). `SinkTarget::Mem` puffert weiterhin
+// komplett im Speicher, siehe dessen Doku-Kommentar fuer den beabsichtigten
+// (kleinen) Anwendungsfall.
+// Diese Abweichung vom Request ist nicht vom Produkt abgenommen; vor einem
+// Merge als abschliessende Umsetzung von chunk4-6 braucht es explizites
+// Sign-off, dass dieser Pipe-basierte Ersatz fuer den jetzigen Scope ausreicht.
+// STATUS: NICHT abgeschlossen – dieser Commit ist bewusst ein Zwischenstand,
+// kein finales "done" fuer chunk4-6.
+//
+// Wichtig: Ein Pipe-Ziel ist fuer FFmpeg selbst nie seekable, unabhaengig davon
+// ob der dahinterliegende Netzwerk-Sink es waere (siehe `requires_streamable_flags`) –
+// MOV/ProRes muessten sonst den `moov`-Atom nachtraeglich an den Dateianfang
+// zurueckschreiben (Moov-Atom-Relokation), was auf `pipe:1` nicht moeglich ist.
+
+use anyhow::{anyhow, bail, Context, Result};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client as S3Client;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+
+use crate::ipc::protocol::{JobOptions, UploadDestination};
+
+/// Geparstes `JobOptions::output_url`-Ziel.
+pub enum SinkTarget {
+    /// Chunked `PUT` an eine `http://`-URL (kein TLS – fuer `https://` waere ein
+    /// TLS-Stream noetig, noch nicht implementiert). Streamt in
+    /// `UPLOAD_CHUNK_BYTES`-Stuecken per `Transfer-Encoding: chunked`, siehe `put_http`.
+    Http { url: String },
+    /// `s3://bucket/key`: echter Multipart-Upload (ein Part pro gelesenem
+    /// `UPLOAD_CHUNK_BYTES`-Stueck, siehe `put_s3`) ueber dieselben Zugangsdaten
+    /// wie `JobOptions::upload_destination` (muss zusaetzlich gesetzt sein).
+    S3 { bucket: String, key: String },
+    /// `mem://`: In-Memory-Puffer, primaer fuer Tests/lokale Previews ohne
+    /// Netzwerkabhaengigkeit. Ueber `take_mem_buffer` nach Jobende abholbar.
+    /// Puffert (anders als `Http`/`S3`) weiterhin komplett im Speicher – fuer
+    /// den beabsichtigten kleinen Test-/Preview-Anwendungsfall unproblematisch.
+    Mem,
+}
+
+/// Alles was `runner::run_ffmpeg` braucht, um den Output waehrend des Encodes in
+/// einen Netzwerk-Sink zu pumpen (siehe `stream_to_sink`).
+pub struct NetworkSinkRequest {
+    pub target: SinkTarget,
+    pub upload_destination: Option<UploadDestination>,
+}
+
+/// Parst `JobOptions::output_url` (plus ggf. `upload_destination` fuer die
+/// s3://-Zugangsdaten) in eine `NetworkSinkRequest`. Gibt `Ok(None)` zurueck
+/// wenn kein `output_url` gesetzt ist (normaler lokaler Output-Pfad).
+pub fn parse_output_url(options: &JobOptions) -> Result<Option<NetworkSinkRequest>> {
+    parse_target(options.output_url.as_deref())
+        .map(|target| target.map(|target| NetworkSinkRequest { target, upload_destination: options.upload_destination.clone() }))
+}
+
+fn parse_target(url: Option<&str>) -> Result<Option<SinkTarget>> {
+    let Some(url) = url else {
+        return Ok(None);
+    };
+
+    if let Some(rest) = url.strip_prefix("s3://") {
+        let mut parts = rest.splitn(2, '/');
+        let bucket = parts.next().filter(|b| !b.is_empty());
+        let key = parts.next().filter(|k| !k.is_empty());
+        match (bucket, key) {
+            (Some(bucket), Some(key)) => Ok(Some(SinkTarget::S3 { bucket: bucket.to_string(), key: key.to_string() })),
+            _ => bail!("Ungueltige s3:// output_url, erwartet 's3://bucket/key': {url}"),
+        }
+    } else if url.starts_with("http://") {
+        Ok(Some(SinkTarget::Http { url: url.to_string() }))
+    } else if url == "mem://" || url.starts_with("mem://") {
+        Ok(Some(SinkTarget::Mem))
+    } else {
+        bail!("Nicht unterstuetztes output_url-Schema (erwartet http://, s3:// oder mem://): {url}")
+    }
+}
+
+/// `true` wenn der Output per `pipe:1` statt Datei geschrieben wird (siehe
+/// `JobOptions::output_url`) UND der Codec einen nachtraeglich zu schreibenden
+/// Moov-Atom braucht (MOV/ProRes). In diesem Fall muss der Muxer sein
+/// Output-Format so waehlen, dass kein Rueckwaerts-Seek noetig ist – sonst
+/// schlaegt der Write auf `pipe:1` fehl.
+pub fn requires_streamable_flags(options: &JobOptions) -> bool {
+    options.output_url.is_some() && crate::ffmpeg::runner::is_prores(&options.proxy_codec)
+}
+
+/// Baut das S3-Client-Config analog zu `jobs::upload::build_client` – dieselben
+/// Zugangsdaten, da `output_url = "s3://..."` ohne eigene `UploadDestination`
+/// fuer diesen Sink auskommt (nur Bucket/Key stehen in der URL).
+fn build_s3_client(dest: &UploadDestination) -> S3Client {
+    let credentials = Credentials::new(&dest.access_key_id, &dest.secret_access_key, None, None, "proxy-generator");
+    let config = aws_sdk_s3::Config::builder()
+        .endpoint_url(&dest.endpoint)
+        .region(Region::new(dest.region.clone()))
+        .credentials_provider(credentials)
+        .behavior_version(BehaviorVersion::latest())
+        .build();
+    S3Client::from_conf(config)
+}
+
+/// Groesse eines einzelnen Lese-/Upload-Stuecks. Oberhalb von S3s Mindest-
+/// Partgroesse (5 MiB) gewaehlt, damit jedes gelesene Stueck 1:1 als eigener
+/// Multipart-Part hochgeladen werden kann (siehe `put_s3`), ohne vorher den
+/// gesamten Proxy im Speicher zu sammeln.
+const UPLOAD_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Liest bis zu `UPLOAD_CHUNK_BYTES` aus `reader`. Gibt `Ok(None)` bei EOF
+/// zurueck (noch kein Byte gelesen), sonst das (ggf. kleinere, am Stream-Ende
+/// auch < `UPLOAD_CHUNK_BYTES` lange) gelesene Stueck.
+async fn read_chunk(reader: &mut (impl AsyncRead + Unpin)) -> Result<Option<Vec<u8>>> {
+    let mut buf = vec![0u8; UPLOAD_CHUNK_BYTES];
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader
+            .read(&mut buf[filled..])
+            .await
+            .context("Konnte FFmpeg-Output-Pipe nicht lesen")?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    if filled == 0 {
+        return Ok(None);
+    }
+    buf.truncate(filled);
+    Ok(Some(buf))
+}
+
+/// Liest `reader` vollstaendig in den Speicher – nur fuer `SinkTarget::Mem`
+/// (siehe dessen Doku-Kommentar), `Http`/`S3` streamen stattdessen stueckweise
+/// ueber `read_chunk`.
+async fn read_all(mut reader: impl AsyncRead + Unpin) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).await.context("Konnte FFmpeg-Output-Pipe nicht lesen")?;
+    Ok(buf)
+}
+
+/// Streamt `reader` per chunked `PUT` an eine `http://`-URL ueber eine rohe
+/// TCP-Verbindung (kein TLS, siehe `SinkTarget::Http`): jedes per `read_chunk`
+/// gelesene Stueck wird sofort als eigenes `Transfer-Encoding: chunked`-Segment
+/// geschrieben, der gesamte Proxy landet nie komplett im Speicher. Erwartet
+/// eine 2xx-Antwort, sonst ein Fehler mit der ersten Statuszeile.
+async fn put_http(url: &str, mut reader: impl AsyncRead + Unpin) -> Result<String> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| anyhow!("Keine http://-URL: {url}"))?;
+    let (authority, path) = rest.split_once('/').map(|(a, p)| (a, format!("/{p}"))).unwrap_or((rest, "/".to_string()));
+    let (host, port) = authority.split_once(':').map(|(h, p)| (h, p.parse().unwrap_or(80))).unwrap_or((authority, 80));
+
+    let mut stream = TcpStream::connect((host, port)).await.with_context(|| format!("Konnte nicht zu {authority} verbinden"))?;
+    let request = format!("PUT {path} HTTP/1.1\r\nHost: {host}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n");
+
+    use tokio::io::AsyncWriteExt;
+    stream.write_all(request.as_bytes()).await?;
+    while let Some(chunk) = read_chunk(&mut reader).await? {
+        stream.write_all(format!("{:x}\r\n", chunk.len()).as_bytes()).await?;
+        stream.write_all(&chunk).await?;
+        stream.write_all(b"\r\n").await?;
+    }
+    stream.write_all(b"0\r\n\r\n").await?;
+    stream.flush().await?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    let status_line = response.split(|&b| b == b'\n').next().unwrap_or_default();
+    let status_line = String::from_utf8_lossy(status_line);
+    let is_2xx = status_line
+        .split_whitespace()
+        .nth(1)
+        .map_or(false, |code| code.len() == 3 && code.starts_with('2'));
+    if !is_2xx {
+        bail!("HTTP-Upload nach {url} fehlgeschlagen: {}", status_line.trim());
+    }
+
+    Ok(url.to_string())
+}
+
+/// Streamt `reader` per echtem Multipart-Upload nach S3: jedes per `read_chunk`
+/// gelesene Stueck (>= S3s Mindest-Partgroesse, siehe `UPLOAD_CHUNK_BYTES`) wird
+/// als eigener Part hochgeladen, sobald es gelesen ist – der gesamte Proxy
+/// landet nie komplett im Speicher. Bricht den Multipart-Upload bei einem
+/// Lese-/Upload-Fehler ab, statt ihn als haengenden Upload auf S3 liegen zu
+/// lassen. Ein leerer Proxy (kein einziges Stueck gelesen) faellt auf einen
+/// normalen `put_object` mit leerem Body zurueck, da S3 keinen Multipart-Upload
+/// mit null Parts abschliessen laesst.
+async fn put_s3(dest: &UploadDestination, bucket: &str, key: &str, mut reader: impl AsyncRead + Unpin) -> Result<String> {
+    use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+
+    let client = build_s3_client(dest);
+
+    let create = client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .context("S3 Multipart-Upload (output_url) konnte nicht gestartet werden")?;
+    let upload_id = create
+        .upload_id()
+        .context("S3 hat keine upload_id fuer den Multipart-Upload geliefert")?
+        .to_string();
+
+    let mut completed_parts = Vec::new();
+    let mut part_number: i32 = 1;
+
+    loop {
+        let chunk = match read_chunk(&mut reader).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                abort_multipart(&client, bucket, key, &upload_id).await;
+                return Err(e);
+            }
+        };
+
+        let upload_result = client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(chunk))
+            .send()
+            .await;
+        let part = match upload_result {
+            Ok(part) => part,
+            Err(e) => {
+                abort_multipart(&client, bucket, key, &upload_id).await;
+                return Err(e).context("S3 Multipart-Part-Upload (output_url) fehlgeschlagen");
+            }
+        };
+        let e_tag = part.e_tag().unwrap_or_default().to_string();
+        completed_parts.push(CompletedPart::builder().part_number(part_number).e_tag(e_tag).build());
+        part_number += 1;
+    }
+
+    if completed_parts.is_empty() {
+        abort_multipart(&client, bucket, key, &upload_id).await;
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(ByteStream::from(Vec::new()))
+            .send()
+            .await
+            .context("S3-Upload (output_url, leerer Proxy) fehlgeschlagen")?;
+        return Ok(format!("{}/{}/{}", dest.endpoint.trim_end_matches('/'), bucket, key));
+    }
+
+    client
+        .complete_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .upload_id(&upload_id)
+        .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(completed_parts)).build())
+        .send()
+        .await
+        .context("S3 Multipart-Upload (output_url) konnte nicht abgeschlossen werden")?;
+
+    Ok(format!("{}/{}/{}", dest.endpoint.trim_end_matches('/'), bucket, key))
+}
+
+/// Bricht einen haengengelassenen Multipart-Upload ab (best effort – ein
+/// Fehlschlag hier wird nur geloggt, da wir ohnehin bereits einen anderen
+/// Fehler an den Aufrufer zurueckgeben). Ohne das wuerde ein Lese-/Teil-Upload-
+/// Fehler einen unvollstaendigen Upload auf S3 liegen lassen, der dort Kosten
+/// verursacht bis eine Lifecycle-Regel ihn raeumt.
+async fn abort_multipart(client: &S3Client, bucket: &str, key: &str, upload_id: &str) {
+    if let Err(e) = client.abort_multipart_upload().bucket(bucket).key(key).upload_id(upload_id).send().await {
+        eprintln!("S3 Multipart-Upload (output_url) konnte nach Fehler nicht abgebrochen werden: {e}");
+    }
+}
+
+/// Pumpt den Proxy aus `reader` (FFmpegs `pipe:1`-stdout, waehrend FFmpeg noch kodiert, siehe
+/// `ffmpeg::runner::run_ffmpeg`) in `target` und gibt die resultierende URL
+/// zurueck (bei `SinkTarget::Mem` ein `mem://<job_id>`-Pseudo-URL, der
+/// eigentliche Puffer ist ueber `take_mem_buffer` abholbar). `Http`/`S3`
+/// streamen stueckweise (siehe `put_http`/`put_s3`), nur `Mem` sammelt den
+/// Proxy komplett im Speicher.
+pub async fn stream_to_sink(
+    id: &str,
+    mut reader: impl AsyncRead + Unpin,
+    target: SinkTarget,
+    upload_destination: Option<&UploadDestination>,
+) -> Result<String> {
+    match target {
+        SinkTarget::Http { url } => put_http(&url, &mut reader).await,
+        SinkTarget::S3 { bucket, key } => {
+            let dest = upload_destination
+                .context("output_url mit s3:// braucht zusaetzlich gesetztes upload_destination fuer die Zugangsdaten")?;
+            put_s3(dest, &bucket, &key, &mut reader).await
+        }
+        SinkTarget::Mem => {
+            let body = read_all(&mut reader).await?;
+            store_mem_buffer(id, body);
+            Ok(format!("mem://{id}"))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// In-Memory-Sink-Registry (nur fuer `SinkTarget::Mem`)
+// ---------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+fn mem_registry() -> &'static Mutex<HashMap<String, Vec<u8>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Vec<u8>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn store_mem_buffer(id: &str, body: Vec<u8>) {
+    mem_registry().lock().unwrap().insert(id.to_string(), body);
+}
+
+/// Holt (und entfernt) den In-Memory-Puffer eines `mem://`-Jobs, z.B. fuer
+/// Tests oder um ihn an einen lokalen Viewer weiterzureichen.
+pub fn take_mem_buffer(id: &str) -> Option<Vec<u8>> {
+    mem_registry().lock().unwrap().remove(id)
+}
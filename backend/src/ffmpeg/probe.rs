@@ -0,0 +1,204 @@
+// Media-Probing: Liest Dauer, Framerate, Codec und Aufloesung einer Quelldatei
+// via `ffprobe -show_format -show_streams` aus. Im Gegensatz zu `probe_braw_metadata`
+// (BRAW-Bridge liefert Metadaten direkt) ist das fuer normale FFmpeg-Jobs der
+// einzige Weg, `calculate_progress` eine sinnvolle Gesamtdauer zu geben.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// Metadaten einer Mediendatei, wie sie `ffprobe` liefert.
+#[derive(Debug, Clone, Default)]
+pub struct MediaMetadata {
+    pub duration_us: i64,
+    pub fps: f32,
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub nb_frames: u64,
+
+    /// Transfer-Charakteristik des ersten Video-Streams (z.B. "smpte2084" fuer
+    /// PQ/HDR10, "arib-std-b67" fuer HLG, "bt709" fuer SDR). Leer wenn von
+    /// ffprobe nicht ermittelbar.
+    pub color_transfer: String,
+
+    /// Farbraum-Primaries des ersten Video-Streams (z.B. "bt2020").
+    pub color_primaries: String,
+
+    /// Matrix-Koeffizienten des ersten Video-Streams (z.B. "bt2020nc").
+    pub color_space: String,
+
+    /// Mastering-Display-Metadaten im fuer `-master_display` erwarteten Format
+    /// (`G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)`), sofern vorhanden (HDR10-Quellen).
+    pub mastering_display: Option<String>,
+
+    /// Content-Light-Level-Metadaten im fuer `-max_cll` erwarteten Format
+    /// ("max_content,max_average"), sofern vorhanden (HDR10-Quellen).
+    pub max_cll: Option<String>,
+}
+
+/// Ruft `ffprobe -show_format -show_streams` auf und parst Dauer (in Mikrosekunden),
+/// Framerate (aus `r_frame_rate`, Format "num/den"), Codec-Name, Aufloesung und
+/// Framezahl (`nb_frames`) des ersten Video-Streams.
+pub async fn probe_media_metadata(input_path: &Path) -> Result<MediaMetadata> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(input_path.as_os_str())
+        .output()
+        .await
+        .context("ffprobe konnte nicht gestartet werden")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe beendet mit Exit-Code: {}",
+            output.status.code().unwrap_or(-1)
+        ));
+    }
+
+    let v: Value = serde_json::from_slice(&output.stdout).context("ffprobe-JSON ungueltig")?;
+
+    let duration_us = v["format"]["duration"]
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .map(|secs| (secs * 1_000_000.0) as i64)
+        .unwrap_or(0);
+
+    let video_stream = v["streams"]
+        .as_array()
+        .and_then(|streams| streams.iter().find(|s| s["codec_type"].as_str() == Some("video")));
+
+    let (fps, codec, width, height, nb_frames, color_transfer, color_primaries, color_space) =
+        match video_stream {
+            Some(stream) => (
+                parse_frame_rate(stream["r_frame_rate"].as_str().unwrap_or("0/1")),
+                stream["codec_name"].as_str().unwrap_or("").to_string(),
+                stream["width"].as_u64().unwrap_or(0) as u32,
+                stream["height"].as_u64().unwrap_or(0) as u32,
+                stream["nb_frames"]
+                    .as_str()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(0),
+                stream["color_transfer"].as_str().unwrap_or("").to_string(),
+                stream["color_primaries"].as_str().unwrap_or("").to_string(),
+                stream["color_space"].as_str().unwrap_or("").to_string(),
+            ),
+            None => (0.0, String::new(), 0, 0, 0, String::new(), String::new(), String::new()),
+        };
+
+    let (mastering_display, max_cll) = video_stream
+        .map(parse_hdr_side_data)
+        .unwrap_or((None, None));
+
+    Ok(MediaMetadata {
+        duration_us,
+        fps,
+        codec,
+        width,
+        height,
+        nb_frames,
+        color_transfer,
+        color_primaries,
+        color_space,
+        mastering_display,
+        max_cll,
+    })
+}
+
+/// Liest Mastering-Display- und Content-Light-Level-Metadaten aus dem
+/// `side_data_list` des Streams (HDR10-Quellen; bei HLG ueblicherweise nicht
+/// vorhanden). Baut die Werte direkt im fuer `-master_display`/`-max_cll`
+/// erwarteten Format zusammen.
+fn parse_hdr_side_data(stream: &Value) -> (Option<String>, Option<String>) {
+    let side_data = match stream["side_data_list"].as_array() {
+        Some(list) => list,
+        None => return (None, None),
+    };
+
+    let mastering_display = side_data
+        .iter()
+        .find(|d| d["side_data_type"].as_str() == Some("Mastering display metadata"))
+        .map(|d| {
+            format!(
+                "G({},{})B({},{})R({},{})WP({},{})L({},{})",
+                d["green_x"].as_str().unwrap_or("0/1"),
+                d["green_y"].as_str().unwrap_or("0/1"),
+                d["blue_x"].as_str().unwrap_or("0/1"),
+                d["blue_y"].as_str().unwrap_or("0/1"),
+                d["red_x"].as_str().unwrap_or("0/1"),
+                d["red_y"].as_str().unwrap_or("0/1"),
+                d["white_point_x"].as_str().unwrap_or("0/1"),
+                d["white_point_y"].as_str().unwrap_or("0/1"),
+                d["max_luminance"].as_str().unwrap_or("0/1"),
+                d["min_luminance"].as_str().unwrap_or("0/1"),
+            )
+        });
+
+    let max_cll = side_data
+        .iter()
+        .find(|d| d["side_data_type"].as_str() == Some("Content light level metadata"))
+        .map(|d| {
+            format!(
+                "{},{}",
+                d["max_content"].as_u64().unwrap_or(0),
+                d["max_average"].as_u64().unwrap_or(0),
+            )
+        });
+
+    (mastering_display, max_cll)
+}
+
+/// Art einer Live-Quelle (siehe `classify_live_source`). Im Gegensatz zu einer
+/// Datei hat sie keine bekannte Gesamtdauer – `probe_media_metadata` wuerde
+/// hier haengen oder nichts Sinnvolles liefern, siehe `transcode::run_queue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveSourceKind {
+    /// RTSP-URL einer IP-Kamera/eines Streaming-Servers ("rtsp://..."/"rtsps://...").
+    Rtsp,
+    /// V4L2-Capture-Device unter Linux ("/dev/videoN").
+    Capture,
+}
+
+/// Erkennt ob `input_path` eine Live-Quelle statt einer Datei ist (RTSP-URL
+/// oder V4L2-Capture-Device), anhand des Pfads selbst – es gibt keinen
+/// separaten "Quelltyp"-Parameter im Protokoll.
+pub fn classify_live_source(input_path: &Path) -> Option<LiveSourceKind> {
+    let raw = input_path.to_string_lossy();
+    if raw.starts_with("rtsp://") || raw.starts_with("rtsps://") {
+        Some(LiveSourceKind::Rtsp)
+    } else if raw.starts_with("/dev/video") {
+        Some(LiveSourceKind::Capture)
+    } else {
+        None
+    }
+}
+
+/// Kurzform von `classify_live_source` fuer Aufrufstellen die nur wissen
+/// muessen ob ueberhaupt eine Live-Quelle vorliegt (z.B. um die ffprobe-Dauer-
+/// ermittlung zu ueberspringen).
+pub fn is_live_source(input_path: &Path) -> bool {
+    classify_live_source(input_path).is_some()
+}
+
+/// Parst `r_frame_rate` im Format "num/den" (z.B. "30000/1001") zu einem f32.
+fn parse_frame_rate(raw: &str) -> f32 {
+    match raw.split_once('/') {
+        Some((num, den)) => {
+            let num: f64 = num.parse().unwrap_or(0.0);
+            let den: f64 = den.parse().unwrap_or(1.0);
+            if den > 0.0 {
+                (num / den) as f32
+            } else {
+                0.0
+            }
+        }
+        None => raw.parse().unwrap_or(0.0),
+    }
+}
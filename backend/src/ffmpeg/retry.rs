@@ -0,0 +1,88 @@
+// Crash-Klassifikation fuer den Retry-Broker (siehe `jobs::transcode::requeue_for_retry`).
+// Ordnet die Fehlermeldung eines gescheiterten FFmpeg-Laufs (Exit-Code + `log_tail`,
+// siehe `runner::build_error_message`) einer von drei Klassen zu, damit der Scheduler
+// entscheiden kann, ob sich ein erneuter Versuch ueberhaupt lohnt und ob dabei von
+// Hardware- auf Software-Encoding herabgestuft werden sollte.
+
+/// Klassifikation eines fehlgeschlagenen FFmpeg-Laufs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// Permanent und durch Retry nicht behebbar (unbekannter Encoder, ungueltiges
+    /// Argument, Input nicht lesbar). Wird sofort als `JobFailedFinal` abgeschlossen,
+    /// unabhaengig von verbleibenden `max_retries`.
+    Fatal,
+    /// Transiente Erschoepfung einer Hardware-Ressource (NVENC-Session-Limit,
+    /// VAAPI-Device busy). Zaehlt gegen `max_retries`; bei wiederholtem Scheitern
+    /// degradiert der naechste Versuch auf Software-Encoding (siehe
+    /// `requeue_for_retry`).
+    RecoverableHardware,
+    /// Sonstiger transienter Fehler (Prozess-Spawn, temporaeres IO). Zaehlt gegen
+    /// `max_retries`, ohne HW-Degradierung.
+    Recoverable,
+}
+
+/// Bekannte fatale Fehlermuster: kein erneuter Versuch kann daran etwas aendern.
+const FATAL_PATTERNS: &[&str] = &[
+    "Unknown encoder",
+    "Unrecognized option",
+    "Invalid argument",
+    "No such file or directory",
+    "Invalid data found when processing input",
+    "moov atom not found",
+    "does not contain any stream",
+];
+
+/// Bekannte transiente Hardware-Fehlermuster (NVENC-Session-Druck, VAAPI-Device busy).
+const RECOVERABLE_HARDWARE_PATTERNS: &[&str] = &[
+    "No capable devices found",
+    "OpenEncodeSessionEx failed",
+    "cannot open encoder before decoder",
+    "Device creation failed",
+    "Cannot load libcuda.so.1",
+    "vaapi_device_init",
+    "Failed to initialise VAAPI connection",
+    "Device or resource busy",
+];
+
+/// Ordnet `message` (typischerweise `build_error_message`'s Ausgabe) einer
+/// `FailureClass` zu. Unbekannte Fehlermuster gelten konservativ als `Recoverable`
+/// statt `Fatal`, damit ein echter transienter Fehler nicht vorschnell als
+/// permanent unencodable gemeldet wird.
+pub fn classify_failure(message: &str) -> FailureClass {
+    if FATAL_PATTERNS.iter().any(|p| message.contains(p)) {
+        return FailureClass::Fatal;
+    }
+    if RECOVERABLE_HARDWARE_PATTERNS.iter().any(|p| message.contains(p)) {
+        return FailureClass::RecoverableHardware;
+    }
+    FailureClass::Recoverable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_known_fatal_patterns() {
+        assert_eq!(
+            classify_failure("FFmpeg beendet mit Exit-Code: 1\n\nUnknown encoder 'foo'"),
+            FailureClass::Fatal
+        );
+    }
+
+    #[test]
+    fn classifies_known_hardware_patterns() {
+        assert_eq!(
+            classify_failure("FFmpeg beendet mit Exit-Code: 1\n\n[hevc_nvenc] OpenEncodeSessionEx failed: out of memory"),
+            FailureClass::RecoverableHardware
+        );
+    }
+
+    #[test]
+    fn defaults_unknown_patterns_to_recoverable() {
+        assert_eq!(
+            classify_failure("FFmpeg beendet mit Exit-Code: 1\n\nsome transient hiccup"),
+            FailureClass::Recoverable
+        );
+    }
+}
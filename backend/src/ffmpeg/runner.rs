@@ -2,16 +2,18 @@
 // Fortschritts-Events ueber einen mpsc channel zurueck.
 
 use anyhow::{Context, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
 use tokio::process::Command;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch, Mutex};
 use tokio_util::sync::CancellationToken;
 
+use crate::ffmpeg::network_sink;
 use crate::ffmpeg::progress::{calculate_progress, ProgressParser};
-use crate::ipc::protocol::{JobMode, JobOptions};
+use crate::ipc::protocol::{JobMode, JobOptions, RateControl};
 
 /// Events die der FFmpeg-Runner an den Job-Manager sendet.
 #[derive(Debug, Clone)]
@@ -22,10 +24,21 @@ pub enum FfmpegEvent {
         fps: f32,
         speed: f32,
         frame: u64,
+        /// Siehe `Response::JobProgress::elapsed_us`. `0` wo keine `out_time_us`
+        /// getrackt wird (Chunked-Encode/BRAW/R3D-Bridge arbeiten frame-basiert).
+        elapsed_us: i64,
     },
     Done {
         id: String,
     },
+    /// Der Output wurde (via `network_sink`) zu einer Netzwerk-URL gestreamt
+    /// statt ausschliesslich lokal zu landen (siehe `JobOptions::output_url`,
+    /// `ffmpeg::network_sink`). Wird immer unmittelbar vor dem abschliessenden
+    /// `Done` fuer denselben Job gesendet.
+    Uploaded {
+        id: String,
+        url: String,
+    },
     Error {
         id: String,
         message: String,
@@ -33,6 +46,98 @@ pub enum FfmpegEvent {
     Cancelled {
         id: String,
     },
+    /// Ein neues Media-Segment wurde fertig geschrieben (siehe
+    /// `ffmpeg::segmented::run_segmented_job`). Kein terminales Event – es folgen
+    /// weitere `SegmentReady` bzw. abschliessend `Done`/`Error`/`Cancelled`.
+    SegmentReady {
+        id: String,
+        path: String,
+        index: u32,
+    },
+}
+
+/// Farb-/HDR-Metadaten fuer Output-Tagging und optionales Tone-Mapping (siehe
+/// `JobOptions::color_transfer`/`color_primaries`/`color_space`/`tonemap`).
+/// Vom Nutzer gesetzte Werte haben Vorrang, Fallback ist der von
+/// `probe::probe_media_metadata` geprobte Wert der Quelle (Container-Tags sind
+/// oft falsch gesetzt, siehe `jobs::transcode::resolve_color_metadata`).
+#[derive(Debug, Clone, Default)]
+pub struct ColorMetadata {
+    pub color_transfer: String,
+    pub color_primaries: String,
+    pub color_space: String,
+    pub mastering_display: Option<String>,
+    pub max_cll: Option<String>,
+    pub tonemap: bool,
+}
+
+/// PQ (HDR10) oder HLG – die beiden Transfer-Charakteristiken, fuer die das
+/// Tonemap-Opt-in tatsaechlich einen Unterschied macht.
+fn is_hdr_transfer(transfer: &str) -> bool {
+    matches!(transfer, "smpte2084" | "arib-std-b67")
+}
+
+/// Zscale-basierte Tonemap-Filterkette fuer den CPU/Software-Bereich (VAAPI-
+/// Hybrid, NVENC-Hybrid, Software-Encoder). Leerer String wenn Tonemap nicht
+/// aktiv ist oder die Quelle gar kein HDR-Transfer hat (no-op).
+fn tonemap_prefix(color: &ColorMetadata) -> &'static str {
+    if color.tonemap && is_hdr_transfer(&color.color_transfer) {
+        "zscale=transfer=linear,tonemap=hable:desat=0,zscale=transfer=bt709:matrix=bt709:primaries=bt709,"
+    } else {
+        ""
+    }
+}
+
+/// `tonemap_cuda`-Praefix fuer den Full-GPU-Pfad (CUDA-Frames bleiben auf der
+/// GPU, kein `zscale` moeglich). Leerer String wenn Tonemap nicht aktiv ist
+/// oder die Quelle kein HDR-Transfer hat.
+///
+/// Nutzt den CUDA-nativen `tonemap_cuda`-Filter statt, wie bei anderen Full-GPU-
+/// Pipelines ueblich, fuer aktives Tonemap auf einen CPU-Decode-Pfad auszuweichen
+/// (`nvenc_full_gpu` bleibt dadurch auch mit Tonemap durchgehend GPU-seitig).
+fn tonemap_cuda_prefix(color: &ColorMetadata) -> &'static str {
+    if color.tonemap && is_hdr_transfer(&color.color_transfer) {
+        "tonemap_cuda=format=nv12,"
+    } else {
+        ""
+    }
+}
+
+/// Setzt Output-Farbmetadaten-Tags (`-color_primaries`/`-color_trc`/`-colorspace`,
+/// sowie `-master_display`/`-max_cll` bei HDR10), damit PQ/HLG-Quellen nicht
+/// stillschweigend auf SDR-Tags degradiert werden. Bei aktivem Tonemap werden
+/// stattdessen BT.709-SDR-Tags gesetzt und Mastering-Display/CLL weggelassen
+/// (nach dem Tonemap nicht mehr gueltig).
+fn push_color_tag_args(args: &mut Vec<String>, color: &ColorMetadata) {
+    if color.tonemap && is_hdr_transfer(&color.color_transfer) {
+        args.push("-color_primaries".to_string());
+        args.push("bt709".to_string());
+        args.push("-color_trc".to_string());
+        args.push("bt709".to_string());
+        args.push("-colorspace".to_string());
+        args.push("bt709".to_string());
+        return;
+    }
+    if !color.color_primaries.is_empty() {
+        args.push("-color_primaries".to_string());
+        args.push(color.color_primaries.clone());
+    }
+    if !color.color_transfer.is_empty() {
+        args.push("-color_trc".to_string());
+        args.push(color.color_transfer.clone());
+    }
+    if !color.color_space.is_empty() {
+        args.push("-colorspace".to_string());
+        args.push(color.color_space.clone());
+    }
+    if let Some(master_display) = &color.mastering_display {
+        args.push("-master_display".to_string());
+        args.push(master_display.clone());
+    }
+    if let Some(max_cll) = &color.max_cll {
+        args.push("-max_cll".to_string());
+        args.push(max_cll.clone());
+    }
 }
 
 /// Normalisiert eine Resolution-Angabe fuer FFmpeg.
@@ -47,16 +152,20 @@ fn normalize_resolution(res: &str) -> String {
 /// 1. -y (overwrite)
 /// 2. HW-Accel Flags VOR -i (VAAPI-Device / CUDA-hwaccel)
 /// 3. -loglevel warning
-/// 4. -i INPUT
-/// 5. Mapping + Codec-Optionen
-/// 6. -progress pipe:2
-/// 7. OUTPUT
+/// 4. Live-Source Input-Flags VOR -i (RTSP-Transport/V4L2-Format, siehe
+///    `push_live_source_input_args`) – ohne Effekt bei Datei-Inputs
+/// 5. -i INPUT
+/// 6. Mapping + Codec-Optionen
+/// 7. -t/-fs Limits (siehe `push_duration_limit_args`), -progress pipe:2
+/// 8. OUTPUT
 pub fn build_ffmpeg_args(
     input_path: &Path,
     output_path: &Path,
     mode: &JobMode,
     options: &JobOptions,
     nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    color: &ColorMetadata,
 ) -> Vec<String> {
     let mut args = Vec::new();
 
@@ -65,38 +174,17 @@ pub fn build_ffmpeg_args(
 
     // HW-Accel Flags VOR -i (nur Proxy, nicht ProRes – ProRes ist immer CPU)
     if matches!(mode, JobMode::Proxy) && !is_prores(&options.proxy_codec) {
-        match options.hw_accel.as_str() {
-            "vaapi" => {
-                args.push("-vaapi_device".to_string());
-                args.push("/dev/dri/renderD128".to_string());
-            }
-            "nvenc" => {
-                // CUDA-Device fuer Filtergraph (benoetigt von hwupload + scale_cuda).
-                args.push("-init_hw_device".to_string());
-                args.push("cuda=cuda:0".to_string());
-                args.push("-filter_hw_device".to_string());
-                args.push("cuda".to_string());
-                if nvenc_full_gpu {
-                    // Volle GPU-Pipeline: NVDEC dekodiert direkt in den GPU-Speicher.
-                    // Frames bleiben auf der GPU – kein PCIe-Transfer noetig.
-                    args.push("-hwaccel".to_string());
-                    args.push("cuda".to_string());
-                    args.push("-hwaccel_device".to_string());
-                    args.push("cuda".to_string());
-                    args.push("-hwaccel_output_format".to_string());
-                    args.push("cuda".to_string());
-                }
-                // Ohne nvenc_full_gpu: CPU-Decode → format=nv12 → hwupload → scale_cuda.
-                // Wird fuer Formate gewaehlt, die NVDEC nicht unterstuetzt (z.B. p210le).
-            }
-            _ => {}
-        }
+        push_hwaccel_prefix(&mut args, &options.hw_accel, nvenc_full_gpu);
     }
 
     // Weniger stderr-Noise
     args.push("-loglevel".to_string());
     args.push("warning".to_string());
 
+    // Live-Quelle (RTSP/V4L2, siehe probe::classify_live_source): Transport-/
+    // Geraete-Flags muessen VOR -i stehen, ohne Effekt bei Datei-Inputs.
+    push_live_source_input_args(&mut args, input_path, options);
+
     // Input
     args.push("-i".to_string());
     args.push(input_path.to_string_lossy().to_string());
@@ -125,7 +213,10 @@ pub fn build_ffmpeg_args(
                 .proxy_resolution
                 .as_deref()
                 .map(normalize_resolution);
-            push_proxy_codec_args(&mut args, &options.proxy_codec, &options.hw_accel, res.as_deref(), nvenc_full_gpu);
+            push_proxy_codec_args(&mut args, &options.proxy_codec, &options.hw_accel, res.as_deref(), nvenc_full_gpu, crf_override, options.rate_control.as_ref(), color);
+
+            // HDR-Metadaten (PQ/HLG) bzw. Tonemap-SDR-Tags auf dem Output-Container
+            push_color_tag_args(&mut args, color);
 
             // Audio bei Proxy: pcm_s16le
             args.push("-c:a".to_string());
@@ -141,18 +232,143 @@ pub fn build_ffmpeg_args(
             // eigene Arg-Logik in r3d::runner::build_r3d_ffmpeg_args
             unreachable!("R3dProxy nutzt eigene FFmpeg-Args via r3d::runner");
         }
+        JobMode::Concat => {
+            // Concat wird nicht ueber build_ffmpeg_args abgewickelt –
+            // eigene Filtergraph-Logik in ffmpeg::concat::run_concat_job
+            unreachable!("Concat nutzt eigene FFmpeg-Args via ffmpeg::concat");
+        }
+        JobMode::Stream => {
+            // Stream wird nicht ueber build_ffmpeg_args abgewickelt –
+            // eigene Muxer-Arg-Logik in ffmpeg::segmented::build_segment_args
+            unreachable!("Stream nutzt eigene FFmpeg-Args via ffmpeg::segmented");
+        }
     }
 
+    // Wall-Clock-/Byte-Limit fuer Quellen ohne bekannte Gesamtdauer (siehe
+    // `JobOptions::max_duration_secs`/`max_bytes`), ohne Effekt wenn nicht gesetzt.
+    push_duration_limit_args(&mut args, options);
+
     // Strukturiertes Progress-Reporting auf stderr
     args.push("-progress".to_string());
     args.push("pipe:2".to_string());
 
+    // Netzwerk-Sink (siehe `JobOptions::output_url`, `ffmpeg::network_sink`):
+    // nur fuer normale Proxy-Jobs sinnvoll, ReWrap ist reiner Stream-Copy in
+    // einen Container-Ziel-Pfad und wird hier bewusst uebergangen.
+    let wants_network_sink = matches!(mode, JobMode::Proxy) && options.output_url.is_some();
+
+    // `pipe:1` statt Datei-Output ist fuer FFmpeg nie seekable, MOV/ProRes
+    // brauchen daher Flags die ohne nachtraegliches Moov-Atom-Rewrite auskommen.
+    if wants_network_sink && network_sink::requires_streamable_flags(options) {
+        args.push("-movflags".to_string());
+        args.push("frag_keyframe+empty_moov+faststream".to_string());
+    }
+
     // Output
-    args.push(output_path.to_string_lossy().to_string());
+    if wants_network_sink {
+        args.push("pipe:1".to_string());
+    } else {
+        args.push(output_path.to_string_lossy().to_string());
+    }
 
     args
 }
 
+/// HW-Accel Flags VOR `-i` (VAAPI-Device / CUDA-hwaccel). Gemeinsam genutzt von
+/// `build_ffmpeg_args` (Proxy) und `ffmpeg::segmented::build_segment_args`
+/// (`JobMode::Stream`) – beide encoden live und brauchen dieselbe Device-Init.
+pub(crate) fn push_hwaccel_prefix(args: &mut Vec<String>, hw_accel: &str, nvenc_full_gpu: bool) {
+    match hw_accel {
+        "vaapi" => {
+            args.push("-vaapi_device".to_string());
+            args.push("/dev/dri/renderD128".to_string());
+        }
+        "nvenc" => {
+            // CUDA-Device fuer Filtergraph (benoetigt von hwupload + scale_cuda).
+            args.push("-init_hw_device".to_string());
+            args.push("cuda=cuda:0".to_string());
+            args.push("-filter_hw_device".to_string());
+            args.push("cuda".to_string());
+            if nvenc_full_gpu {
+                // Volle GPU-Pipeline: NVDEC dekodiert direkt in den GPU-Speicher.
+                // Frames bleiben auf der GPU – kein PCIe-Transfer noetig.
+                args.push("-hwaccel".to_string());
+                args.push("cuda".to_string());
+                args.push("-hwaccel_device".to_string());
+                args.push("cuda".to_string());
+                args.push("-hwaccel_output_format".to_string());
+                args.push("cuda".to_string());
+            }
+            // Ohne nvenc_full_gpu: CPU-Decode → format=nv12 → hwupload → scale_cuda.
+            // Wird fuer Formate gewaehlt, die NVDEC nicht unterstuetzt (z.B. p210le).
+        }
+        _ => {}
+    }
+}
+
+/// Fuegt Input-spezifische Flags VOR `-i` fuer Live-Quellen ein (siehe
+/// `probe::classify_live_source`). Ohne Effekt bei Datei-Inputs. Gemeinsam
+/// genutzt von `build_ffmpeg_args` (Proxy/ReWrap) und
+/// `ffmpeg::segmented::build_segment_args` (`JobMode::Stream`).
+pub(crate) fn push_live_source_input_args(args: &mut Vec<String>, input_path: &Path, options: &JobOptions) {
+    match crate::ffmpeg::probe::classify_live_source(input_path) {
+        Some(crate::ffmpeg::probe::LiveSourceKind::Rtsp) => {
+            args.push("-rtsp_transport".to_string());
+            args.push(options.rtsp_transport.clone());
+            if options.rtsp_reconnect {
+                // Automatischer Re-Connect bei Verbindungsabbruch (Kamera-Reboot,
+                // kurzer Netzwerk-Hickup) statt den Job sofort als fehlgeschlagen
+                // zu beenden – der Job laeuft ohnehin bis Cancel/Limit weiter.
+                args.push("-reconnect".to_string());
+                args.push("1".to_string());
+                args.push("-reconnect_streamed".to_string());
+                args.push("1".to_string());
+                args.push("-reconnect_delay_max".to_string());
+                args.push("5".to_string());
+            }
+        }
+        Some(crate::ffmpeg::probe::LiveSourceKind::Capture) => {
+            args.push("-f".to_string());
+            args.push("v4l2".to_string());
+            if let Some(pix_fmt) = options.capture_pix_fmt.as_deref().filter(|s| !s.is_empty()) {
+                args.push("-input_format".to_string());
+                args.push(pix_fmt.to_string());
+            }
+            if let Some(res) = options.capture_resolution.as_deref().filter(|s| !s.is_empty()) {
+                args.push("-video_size".to_string());
+                args.push(res.to_string());
+            }
+        }
+        None => {}
+    }
+}
+
+/// Fuegt `-t`/`-fs` Limits ein (siehe `JobOptions::max_duration_secs`/`max_bytes`):
+/// relevant vor allem fuer Live-Quellen ohne bekannte Gesamtdauer, die sonst nur
+/// per `Cancel` beendet werden koennten. Ohne Effekt wenn nicht gesetzt.
+pub(crate) fn push_duration_limit_args(args: &mut Vec<String>, options: &JobOptions) {
+    if let Some(max_duration_secs) = options.max_duration_secs {
+        args.push("-t".to_string());
+        args.push(max_duration_secs.to_string());
+    }
+    if let Some(max_bytes) = options.max_bytes {
+        args.push("-fs".to_string());
+        args.push(max_bytes.to_string());
+    }
+}
+
+/// Ersetzt den Wert hinter `-progress` in einer bereits gebauten Argumentliste
+/// (Default aus `build_ffmpeg_args` ist `pipe:2`). Wird vom Runner genutzt, um
+/// kurz vor dem Spawn auf `tcp://127.0.0.1:<port>` umzuschalten, sobald der
+/// Listener-Port bekannt ist.
+fn set_progress_target(args: &mut [String], target: &str) {
+    if let Some(idx) = args.iter().position(|a| a == "-progress") {
+        if let Some(value) = args.get_mut(idx + 1) {
+            *value = target.to_string();
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Codec-Hilfsfunktionen
 // ---------------------------------------------------------------------------
@@ -162,154 +378,344 @@ pub fn is_prores(codec: &str) -> bool {
 }
 
 /// Waehlt den passenden Video-Encoder anhand von proxy_codec × hw_accel.
+///
+/// `crf_override` ersetzt die feste CRF/QP der Encoder-Backends durch einen
+/// konvergierten Wert aus `ffmpeg::vmaf::converge_crf` (siehe
+/// `JobOptions::target_vmaf`): bei den Software-Pfaden (`push_sw_x264`/
+/// `push_sw_x265`/`push_sw_av1`) direkt als CRF, bei VAAPI/NVENC als
+/// `RateControl::ConstQp` (siehe `push_vaapi`/`push_nvenc`/`push_nvenc_av1`) – beide
+/// Groessen sind fuer `converge_crf` nur verschiedene Zahlen auf derselben
+/// Probe-Konvergenz. ProRes (festes Profil) ignoriert ihn weiterhin.
+/// Hat Vorrang vor `rate_control`, falls beide gesetzt sind.
+///
+/// `rate_control` waehlt VBR/CBR/Zwei-Pass statt der festen QP/CRF (siehe
+/// `JobOptions::rate_control`). Ohne Effekt bei ProRes. Der eigentliche
+/// Zwei-Pass-Doppellauf (Pass 1/2) wird nicht hier, sondern von
+/// `run_two_pass_ffmpeg` orchestriert – hier wird bei `RateControl::TwoPass` nur
+/// die Ziel-Bitrate als `-b:v` gesetzt.
+///
+/// `color` steuert das optionale Tone-Mapping (siehe `JobOptions::tonemap`): greift
+/// nur wenn die Quelle tatsaechlich PQ/HLG ist, sonst bleibt die Filterkette
+/// unveraendert (no-op).
+#[allow(clippy::too_many_arguments)]
 pub fn push_proxy_codec_args(
     args: &mut Vec<String>,
     proxy_codec: &str,
     hw_accel: &str,
     resolution: Option<&str>,
     nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    rate_control: Option<&RateControl>,
+    color: &ColorMetadata,
 ) {
+    // `target_vmaf` konvergiert bereits auf einen QP/CRF-Wert – eine zusaetzliche
+    // `rate_control`-Vorgabe waere widerspruechlich, daher gewinnt `crf_override`.
+    // Fuer VAAPI/NVENC ist der konvergierte Wert ein QP (siehe
+    // `ffmpeg::vmaf::converge_crf`s `hw_accel`-Parameter), kein CRF – daher als
+    // `RateControl::ConstQp` statt ueber `crf_override` selbst eingespeist, den nur
+    // die SW-Pfade (`push_sw_x264`/`push_sw_x265`/`push_sw_av1`) konsumieren.
+    let hw_qp_override = crf_override.map(|qp| RateControl::ConstQp { qp });
+    let hw_rate_control = hw_qp_override.as_ref().or(rate_control);
+    let rate_control = if crf_override.is_some() { None } else { rate_control };
     match proxy_codec {
         // ── H.264 ──────────────────────────────────────────────────────────
         "h264" => match hw_accel {
-            "vaapi" => push_vaapi(args, "h264_vaapi", resolution),
-            "nvenc" => push_nvenc(args, "h264_nvenc", "23", resolution, nvenc_full_gpu),
-            _       => push_sw_x264(args, resolution),
+            "vaapi" => push_vaapi(args, "h264_vaapi", resolution, hw_rate_control, color),
+            "nvenc" => push_nvenc(args, "h264_nvenc", "23", resolution, nvenc_full_gpu, hw_rate_control, color),
+            _       => push_sw_x264(args, resolution, crf_override, rate_control, color),
         },
         // ── H.265 / HEVC ───────────────────────────────────────────────────
         "h265" => match hw_accel {
-            "vaapi" => push_vaapi(args, "hevc_vaapi", resolution),
-            "nvenc" => push_nvenc(args, "hevc_nvenc", "23", resolution, nvenc_full_gpu),
-            _       => push_sw_x265(args, resolution),
+            "vaapi" => push_vaapi(args, "hevc_vaapi", resolution, hw_rate_control, color),
+            "nvenc" => push_nvenc(args, "hevc_nvenc", "23", resolution, nvenc_full_gpu, hw_rate_control, color),
+            _       => push_sw_x265(args, resolution, crf_override, rate_control, color),
         },
         // ── AV1 ────────────────────────────────────────────────────────────
         "av1" => match hw_accel {
-            "vaapi" => push_vaapi(args, "av1_vaapi", resolution),
+            "vaapi" => push_vaapi(args, "av1_vaapi", resolution, hw_rate_control, color),
             // AV1 NVENC: braucht yuv420p (keine CUDA-Frames) + SW-scale statt scale_cuda
-            "nvenc" => push_nvenc_av1(args, resolution),
-            _       => push_sw_av1(args, resolution),
+            "nvenc" => push_nvenc_av1(args, resolution, hw_rate_control, color),
+            _       => push_sw_av1(args, resolution, crf_override, rate_control, color),
         },
         // ── ProRes ─────────────────────────────────────────────────────────
+        // Festes Profil statt Ratenkontrolle – `rate_control` wird hier ignoriert.
         c if is_prores(c) => push_prores(args, c, resolution),
         // ── Fallback: libx264 ──────────────────────────────────────────────
-        _ => push_sw_x264(args, resolution),
+        _ => push_sw_x264(args, resolution, crf_override, rate_control, color),
+    }
+}
+
+/// Der tatsaechliche FFmpeg-Encoder-Name fuer eine `proxy_codec x hw_accel`
+/// Kombination, wie sie `push_proxy_codec_args` waehlen wuerde. `None` heisst:
+/// kein dedizierter HW-Encoder im Spiel (Software-Pfad oder ProRes) – dafuer gibt
+/// es nichts zu pruefen, diese Encoder sind immer verfuegbar. Wird von
+/// `hwcaps::HwCapabilities::resolve_hw_accel` genutzt, um vor dem eigentlichen
+/// Spawn denselben Namen gegen `ffmpeg -encoders` zu pruefen, der tatsaechlich
+/// uebergeben wuerde.
+pub fn encoder_name(proxy_codec: &str, hw_accel: &str) -> Option<&'static str> {
+    match (proxy_codec, hw_accel) {
+        ("h264", "vaapi") => Some("h264_vaapi"),
+        ("h264", "nvenc") => Some("h264_nvenc"),
+        ("h265", "vaapi") => Some("hevc_vaapi"),
+        ("h265", "nvenc") => Some("hevc_nvenc"),
+        ("av1", "vaapi")  => Some("av1_vaapi"),
+        ("av1", "nvenc")  => Some("av1_nvenc"),
+        _ => None,
+    }
+}
+
+/// Haengt die `-b:v`/`-maxrate`/`-bufsize`-Trias fuer VBR/CBR/Zwei-Pass an,
+/// gemeinsam genutzt von allen Encoder-Backends (VAAPI/NVENC/SW). Gibt `true`
+/// zurueck wenn `rate_control` behandelt wurde (Aufrufer soll dann **kein**
+/// zusaetzliches QP/CRF setzen).
+fn push_bitrate_rate_control(args: &mut Vec<String>, rate_control: Option<&RateControl>) -> bool {
+    match rate_control {
+        Some(RateControl::Vbr { target_kbps, max_kbps }) => {
+            args.push("-b:v".to_string());
+            args.push(format!("{target_kbps}k"));
+            args.push("-maxrate".to_string());
+            args.push(format!("{max_kbps}k"));
+            args.push("-bufsize".to_string());
+            args.push(format!("{}k", max_kbps * 2));
+            true
+        }
+        Some(RateControl::Cbr { kbps }) => {
+            args.push("-b:v".to_string());
+            args.push(format!("{kbps}k"));
+            args.push("-maxrate".to_string());
+            args.push(format!("{kbps}k"));
+            args.push("-bufsize".to_string());
+            args.push(format!("{}k", kbps * 2));
+            true
+        }
+        // Der eigentliche Zwei-Pass-Doppellauf liegt bei `run_two_pass_ffmpeg` – hier
+        // reicht die Ziel-Bitrate als `-b:v`, `-pass N`/`-passlogfile` haengt der
+        // Zwei-Pass-Wrapper selbst an (siehe dort).
+        Some(RateControl::TwoPass { target_kbps }) => {
+            args.push("-b:v".to_string());
+            args.push(format!("{target_kbps}k"));
+            true
+        }
+        Some(RateControl::ConstQp { .. }) | None => false,
     }
 }
 
 /// VAAPI-Encoder (h264_vaapi / hevc_vaapi / av1_vaapi).
 /// Benoetigt format=nv12,hwupload fuer den Video-Filter.
-fn push_vaapi(args: &mut Vec<String>, codec: &str, resolution: Option<&str>) {
+fn push_vaapi(args: &mut Vec<String>, codec: &str, resolution: Option<&str>, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push(codec.to_string());
-    args.push("-rc_mode".to_string());
-    args.push("CQP".to_string());
-    args.push("-qp".to_string());
-    args.push("23".to_string());
+    match rate_control {
+        Some(RateControl::Vbr { .. }) | Some(RateControl::TwoPass { .. }) => {
+            args.push("-rc_mode".to_string());
+            args.push("VBR".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::Cbr { .. }) => {
+            args.push("-rc_mode".to_string());
+            args.push("CBR".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::ConstQp { qp }) => {
+            args.push("-rc_mode".to_string());
+            args.push("CQP".to_string());
+            args.push("-qp".to_string());
+            args.push(qp.to_string());
+        }
+        None => {
+            args.push("-rc_mode".to_string());
+            args.push("CQP".to_string());
+            args.push("-qp".to_string());
+            args.push("23".to_string());
+        }
+    }
     args.push("-vf".to_string());
+    let prefix = tonemap_prefix(color);
     match resolution {
-        Some(res) => args.push(format!("format=nv12,hwupload,scale_vaapi={res}")),
-        None      => args.push("format=nv12,hwupload".to_string()),
+        Some(res) => args.push(format!("{prefix}format=nv12,hwupload,scale_vaapi={res}")),
+        None      => args.push(format!("{prefix}format=nv12,hwupload")),
     }
 }
 
 /// NVENC-Encoder (h264_nvenc / hevc_nvenc).
 /// CPU-Decode → format=nv12 (beliebiges Eingangsformat) → hwupload (CUDA) →
 /// scale_cuda (GPU-Skalierung) → NVENC-Encode.
-fn push_nvenc(args: &mut Vec<String>, codec: &str, qp: &str, resolution: Option<&str>, full_gpu: bool) {
+fn push_nvenc(args: &mut Vec<String>, codec: &str, qp: &str, resolution: Option<&str>, full_gpu: bool, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push(codec.to_string());
     args.push("-preset".to_string());
     args.push("p4".to_string());
-    args.push("-rc".to_string());
-    args.push("constqp".to_string());
-    args.push("-qp".to_string());
-    args.push(qp.to_string());
+    match rate_control {
+        Some(RateControl::Vbr { .. }) | Some(RateControl::TwoPass { .. }) => {
+            args.push("-rc".to_string());
+            args.push("vbr".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::Cbr { .. }) => {
+            args.push("-rc".to_string());
+            args.push("cbr".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::ConstQp { qp }) => {
+            args.push("-rc".to_string());
+            args.push("constqp".to_string());
+            args.push("-qp".to_string());
+            args.push(qp.to_string());
+        }
+        None => {
+            args.push("-rc".to_string());
+            args.push("constqp".to_string());
+            args.push("-qp".to_string());
+            args.push(qp.to_string());
+        }
+    }
     if full_gpu {
         // CUDA-Frames direkt von NVDEC → scale_cuda → NVENC, kein PCIe-Transfer.
+        // Tonemap laeuft hier ueber tonemap_cuda (zscale funktioniert nicht auf
+        // CUDA-Frames), siehe `tonemap_cuda_prefix`.
+        let prefix = tonemap_cuda_prefix(color);
         if let Some(res) = resolution {
             args.push("-vf".to_string());
-            args.push(format!("scale_cuda={res}"));
+            args.push(format!("{prefix}scale_cuda={res}"));
+        } else if !prefix.is_empty() {
+            args.push("-vf".to_string());
+            args.push(prefix.trim_end_matches(',').to_string());
         }
-        // Ohne Skalierung: CUDA-Frames gehen direkt an NVENC, kein -vf noetig.
+        // Ohne Skalierung und ohne Tonemap: CUDA-Frames gehen direkt an NVENC, kein -vf noetig.
     } else {
-        // Hybrid: CPU-Decode → format=nv12 (konvertiert auch p210le etc.) →
-        // hwupload → scale_cuda → NVENC.
+        // Hybrid: CPU-Decode → (optional zscale/tonemap) → format=nv12 (konvertiert
+        // auch p210le etc.) → hwupload → scale_cuda → NVENC.
+        let prefix = tonemap_prefix(color);
         if let Some(res) = resolution {
             args.push("-vf".to_string());
-            args.push(format!("format=nv12,hwupload,scale_cuda={res}"));
+            args.push(format!("{prefix}format=nv12,hwupload,scale_cuda={res}"));
         } else {
             // Kein Scale: format=nv12 konvertiert RGB/YUV → nv12,
             // hwupload laedt die Frames in den CUDA-Speicher fuer NVENC.
             args.push("-vf".to_string());
-            args.push("format=nv12,hwupload".to_string());
+            args.push(format!("{prefix}format=nv12,hwupload"));
         }
     }
 }
 
 /// AV1 NVENC: braucht Systemspeicher-Frames (kein CUDA-Input) und yuv420p.
 /// Skalierung daher via Software-scale, nicht scale_cuda.
-fn push_nvenc_av1(args: &mut Vec<String>, resolution: Option<&str>) {
+fn push_nvenc_av1(args: &mut Vec<String>, resolution: Option<&str>, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push("av1_nvenc".to_string());
     args.push("-preset".to_string());
     args.push("p4".to_string());
-    args.push("-rc".to_string());
-    args.push("constqp".to_string());
-    args.push("-qp".to_string());
-    args.push("63".to_string());
+    match rate_control {
+        Some(RateControl::Vbr { .. }) | Some(RateControl::TwoPass { .. }) => {
+            args.push("-rc".to_string());
+            args.push("vbr".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::Cbr { .. }) => {
+            args.push("-rc".to_string());
+            args.push("cbr".to_string());
+            push_bitrate_rate_control(args, rate_control);
+        }
+        Some(RateControl::ConstQp { qp }) => {
+            args.push("-rc".to_string());
+            args.push("constqp".to_string());
+            args.push("-qp".to_string());
+            args.push(qp.to_string());
+        }
+        None => {
+            args.push("-rc".to_string());
+            args.push("constqp".to_string());
+            args.push("-qp".to_string());
+            args.push("63".to_string());
+        }
+    }
     args.push("-pix_fmt".to_string());
     args.push("yuv420p".to_string());
+    let prefix = tonemap_prefix(color);
     if let Some(res) = resolution {
         args.push("-vf".to_string());
-        args.push(format!("scale={res}"));
+        args.push(format!("{prefix}scale={res}"));
+    } else if !prefix.is_empty() {
+        args.push("-vf".to_string());
+        args.push(prefix.trim_end_matches(',').to_string());
     }
 }
 
 /// Software H.264 (libx264).
-fn push_sw_x264(args: &mut Vec<String>, resolution: Option<&str>) {
+fn push_sw_x264(args: &mut Vec<String>, resolution: Option<&str>, crf_override: Option<u32>, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push("libx264".to_string());
-    args.push("-crf".to_string());
-    args.push("23".to_string());
+    if !push_bitrate_rate_control(args, rate_control) {
+        let qp = match rate_control {
+            Some(RateControl::ConstQp { qp }) => *qp,
+            _ => crf_override.unwrap_or(23),
+        };
+        args.push("-crf".to_string());
+        args.push(qp.to_string());
+    }
     args.push("-preset".to_string());
     args.push("fast".to_string());
     args.push("-pix_fmt".to_string());
     args.push("yuv420p".to_string());
+    let prefix = tonemap_prefix(color);
     if let Some(res) = resolution {
         args.push("-vf".to_string());
-        args.push(format!("scale={res}"));
+        args.push(format!("{prefix}scale={res}"));
+    } else if !prefix.is_empty() {
+        args.push("-vf".to_string());
+        args.push(prefix.trim_end_matches(',').to_string());
     }
 }
 
 /// Software H.265 (libx265).
-fn push_sw_x265(args: &mut Vec<String>, resolution: Option<&str>) {
+fn push_sw_x265(args: &mut Vec<String>, resolution: Option<&str>, crf_override: Option<u32>, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push("libx265".to_string());
-    args.push("-crf".to_string());
-    args.push("23".to_string());
+    if !push_bitrate_rate_control(args, rate_control) {
+        let qp = match rate_control {
+            Some(RateControl::ConstQp { qp }) => *qp,
+            _ => crf_override.unwrap_or(23),
+        };
+        args.push("-crf".to_string());
+        args.push(qp.to_string());
+    }
     args.push("-preset".to_string());
     args.push("fast".to_string());
     args.push("-pix_fmt".to_string());
     args.push("yuv420p".to_string());
+    let prefix = tonemap_prefix(color);
     if let Some(res) = resolution {
         args.push("-vf".to_string());
-        args.push(format!("scale={res}"));
+        args.push(format!("{prefix}scale={res}"));
+    } else if !prefix.is_empty() {
+        args.push("-vf".to_string());
+        args.push(prefix.trim_end_matches(',').to_string());
     }
 }
 
 /// Software AV1 (libsvtav1 – schnellster freier AV1-Encoder).
-fn push_sw_av1(args: &mut Vec<String>, resolution: Option<&str>) {
+fn push_sw_av1(args: &mut Vec<String>, resolution: Option<&str>, crf_override: Option<u32>, rate_control: Option<&RateControl>, color: &ColorMetadata) {
     args.push("-c:v".to_string());
     args.push("libsvtav1".to_string());
-    args.push("-crf".to_string());
-    args.push("30".to_string());
+    if !push_bitrate_rate_control(args, rate_control) {
+        let qp = match rate_control {
+            Some(RateControl::ConstQp { qp }) => *qp,
+            _ => crf_override.unwrap_or(30),
+        };
+        args.push("-crf".to_string());
+        args.push(qp.to_string());
+    }
     args.push("-preset".to_string());
     args.push("8".to_string());
     args.push("-pix_fmt".to_string());
     args.push("yuv420p".to_string());
+    let prefix = tonemap_prefix(color);
     if let Some(res) = resolution {
         args.push("-vf".to_string());
-        args.push(format!("scale={res}"));
+        args.push(format!("{prefix}scale={res}"));
+    } else if !prefix.is_empty() {
+        args.push("-vf".to_string());
+        args.push(prefix.trim_end_matches(',').to_string());
     }
 }
 
@@ -340,21 +746,55 @@ fn push_prores(args: &mut Vec<String>, codec: &str, resolution: Option<&str>) {
 /// * `args` – Komplette FFmpeg-Argumentliste
 /// * `output_path` – Pfad zur Output-Datei; wird bei Fehler-Exit geloescht (partial file cleanup)
 /// * `total_duration_us` – Gesamtdauer der Quelldatei in Mikrosekunden (fuer Prozentberechnung)
+/// * `progress_via_tcp` – `-progress` ueber lokalen TcpListener statt interleaved auf stderr
+/// * `network_sink` – Bei `Some`: Output laeuft ueber `pipe:1` (siehe
+///   `build_ffmpeg_args`) statt `output_path` und wird parallel zum Progress-
+///   Tracking in den gegebenen Sink gepumpt (siehe `ffmpeg::network_sink`).
 /// * `tx` – Channel fuer Events
 /// * `cancel` – CancellationToken zum Abbrechen
+/// * `paused_rx` – Pause-Status aus `jobs::transcode::PidSet` (siehe
+///   `PidSet::paused_rx`): haelt den Stall-Watchdog waehrend einer per
+///   `JobCommand::PauseJob`/`PauseAll` ausgeloesten SIGSTOP-Pause an, statt den
+///   Job nach `process_timeout_secs` faelschlich als haengend zu killen.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_ffmpeg(
     job_id: String,
     args: Vec<String>,
     output_path: &Path,
     total_duration_us: i64,
+    process_timeout_secs: u64,
+    progress_via_tcp: bool,
+    network_sink: Option<network_sink::NetworkSinkRequest>,
     tx: mpsc::Sender<FfmpegEvent>,
     cancel: CancellationToken,
     pid_slot: Arc<AtomicU32>,
+    mut paused_rx: watch::Receiver<bool>,
 ) -> Result<()> {
+    let mut args = args;
+
+    // Progress-Transport: per Default interleaved auf stderr (-progress pipe:2, siehe
+    // build_ffmpeg_args). Bei `progress_via_tcp` binden wir VOR dem Spawn einen lokalen
+    // TcpListener (sonst wuerde FFmpeg sich ins Leere verbinden) und patchen das
+    // `-progress`-Argument auf den tatsaechlichen Port.
+    let progress_listener = if progress_via_tcp {
+        match TcpListener::bind("127.0.0.1:0").await {
+            Ok(listener) => match listener.local_addr() {
+                Ok(addr) => {
+                    set_progress_target(&mut args, &format!("tcp://127.0.0.1:{}", addr.port()));
+                    Some(listener)
+                }
+                Err(_) => None,
+            },
+            Err(_) => None,
+        }
+    } else {
+        None
+    };
+
     let mut child = Command::new("ffmpeg")
         .args(&args)
         .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::null())
+        .stdout(if network_sink.is_some() { std::process::Stdio::piped() } else { std::process::Stdio::null() })
         .stderr(std::process::Stdio::piped())
         .spawn()
         .context("FFmpeg konnte nicht gestartet werden")?;
@@ -362,6 +802,22 @@ pub async fn run_ffmpeg(
     // PID registrieren damit Pause/Resume den Prozess signalisieren kann
     pid_slot.store(child.id().unwrap_or(0), Ordering::Release);
 
+    // Netzwerk-Sink (siehe `JobOptions::output_url`): FFmpeg schreibt auf
+    // `pipe:1`, das muss parallel zum Progress-Tracking gedraint werden, sonst
+    // blockiert FFmpeg sobald der OS-Pipe-Puffer voll ist. Der Pump-Task laeuft
+    // bis FFmpeg `pipe:1` schliesst; sein Ergebnis wird erst nach erfolgreichem
+    // Exit abgewartet (siehe `finish_success`).
+    let mut sink_handle = match network_sink {
+        Some(req) => {
+            let stdout = child.stdout.take().context("Konnte stdout von FFmpeg nicht lesen (network_sink)")?;
+            let job_id = job_id.clone();
+            Some(tokio::spawn(async move {
+                network_sink::stream_to_sink(&job_id, stdout, req.target, req.upload_destination.as_ref()).await
+            }))
+        }
+        None => None,
+    };
+
     let stderr = child
         .stderr
         .take()
@@ -369,10 +825,79 @@ pub async fn run_ffmpeg(
 
     let mut stdin = child.stdin.take();
 
-    let mut reader = BufReader::new(stderr).lines();
+    // Letzte Zeilen aus FFmpeg-stderr fuer Fehlermeldungen (max. 20). Hinter einem Mutex,
+    // weil im TCP-Modus ein eigener Hintergrund-Task stderr rein diagnostisch mitliest.
+    let log_tail: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::with_capacity(20)));
+
+    // Quelle fuer Progress-Zeilen: im Pipe-Modus stderr selbst (wie bisher), im TCP-Modus
+    // der akzeptierte Socket. Accept hat einen kurzen Timeout und faellt sauber auf
+    // "stderr bleibt einzige Quelle" zurueck, falls FFmpeg sich nicht verbindet
+    // (z.B. ein Build ohne `-progress tcp://` Unterstuetzung).
+    let mut reader: Box<dyn AsyncBufRead + Unpin + Send> = match progress_listener {
+        Some(listener) => {
+            match tokio::time::timeout(tokio::time::Duration::from_secs(5), listener.accept()).await {
+                Ok(Ok((stream, _))) => {
+                    let log_tail_bg = log_tail.clone();
+                    tokio::spawn(async move {
+                        let mut diag = BufReader::new(stderr).lines();
+                        while let Ok(Some(line)) = diag.next_line().await {
+                            let mut tail = log_tail_bg.lock().await;
+                            if tail.len() == 20 {
+                                tail.remove(0);
+                            }
+                            tail.push(line);
+                        }
+                    });
+                    Box::new(BufReader::new(stream))
+                }
+                _ => Box::new(BufReader::new(stderr)),
+            }
+        }
+        None => Box::new(BufReader::new(stderr)),
+    };
+    let mut reader = reader.lines();
     let mut parser = ProgressParser::new();
-    // Letzte Zeilen aus FFmpeg-stderr fuer Fehlermeldungen (max. 20)
-    let mut log_tail: Vec<String> = Vec::with_capacity(20);
+
+    // Stall-Watchdog: wird bei jedem Progress-Block mit gestiegener `frame`-Zahl
+    // zurueckgesetzt. `0` = deaktiviert
+    // (schlaeft dann effektiv "fuer immer", ohne je abzulaufen).
+    let watchdog_duration = if process_timeout_secs > 0 {
+        tokio::time::Duration::from_secs(process_timeout_secs)
+    } else {
+        tokio::time::Duration::from_secs(u64::MAX / 2)
+    };
+    let watchdog = tokio::time::sleep(watchdog_duration);
+    tokio::pin!(watchdog);
+    let mut last_frame: u64 = 0;
+
+    // Wird bei erfolgreichem Exit aufgerufen: wartet auf den Netzwerk-Sink-Pump
+    // (falls vorhanden), meldet `Uploaded` vor dem abschliessenden `Done`.
+    async fn finish_success(
+        tx: &mpsc::Sender<FfmpegEvent>,
+        job_id: &str,
+        sink_handle: Option<tokio::task::JoinHandle<Result<String>>>,
+    ) {
+        if let Some(handle) = sink_handle {
+            match handle.await {
+                Ok(Ok(url)) => {
+                    let _ = tx.send(FfmpegEvent::Uploaded { id: job_id.to_string(), url }).await;
+                }
+                Ok(Err(e)) => {
+                    let _ = tx
+                        .send(FfmpegEvent::Error { id: job_id.to_string(), message: format!("Netzwerk-Sink fehlgeschlagen: {e}") })
+                        .await;
+                    return;
+                }
+                Err(e) => {
+                    let _ = tx
+                        .send(FfmpegEvent::Error { id: job_id.to_string(), message: format!("Netzwerk-Sink-Task Panik: {e}") })
+                        .await;
+                    return;
+                }
+            }
+        }
+        let _ = tx.send(FfmpegEvent::Done { id: job_id.to_string() }).await;
+    }
 
     loop {
         tokio::select! {
@@ -384,6 +909,7 @@ pub async fn run_ffmpeg(
                 }
                 let _ = child.wait().await;
                 pid_slot.store(0, Ordering::Release);
+                if let Some(h) = sink_handle.take() { h.abort(); }
                 let _ = tx
                     .send(FfmpegEvent::Cancelled {
                         id: job_id.clone(),
@@ -391,6 +917,30 @@ pub async fn run_ffmpeg(
                     .await;
                 return Ok(());
             }
+            Ok(()) = paused_rx.changed() => {
+                // Pause/Resume (siehe `PidSet::set_paused`): waehrend SIGSTOP soll der
+                // Watchdog gar nicht erst ablaufen koennen (siehe Guard unten); bei
+                // Resume zaehlt er ab hier nochmal die volle Dauer, statt die waehrend
+                // der Pause verstrichene Zeit gegen den pausierten Prozess zu werten.
+                if !*paused_rx.borrow() {
+                    watchdog.as_mut().reset(tokio::time::Instant::now() + watchdog_duration);
+                }
+            }
+            () = &mut watchdog, if process_timeout_secs > 0 && !*paused_rx.borrow() => {
+                // Kein Fortschritt innerhalb von `process_timeout_secs` – Prozess haengt.
+                let _ = child.kill().await;
+                let _ = child.wait().await; // Zombie verhindern
+                pid_slot.store(0, Ordering::Release);
+                if let Some(h) = sink_handle.take() { h.abort(); }
+                let _ = std::fs::remove_file(output_path); // partial file cleanup
+                let _ = tx
+                    .send(FfmpegEvent::Error {
+                        id: job_id.clone(),
+                        message: "timeout".to_string(),
+                    })
+                    .await;
+                return Ok(());
+            }
             line = reader.next_line() => {
                 match line {
                     Ok(Some(line)) => {
@@ -400,17 +950,16 @@ pub async fn run_ffmpeg(
                                 let status = child.wait().await?;
                                 pid_slot.store(0, Ordering::Release);
                                 if status.success() {
-                                    let _ = tx
-                                        .send(FfmpegEvent::Done { id: job_id.clone() })
-                                        .await;
+                                    finish_success(&tx, &job_id, sink_handle.take()).await;
                                 } else {
+                                    if let Some(h) = sink_handle.take() { h.abort(); }
                                     let _ = std::fs::remove_file(output_path); // partial file cleanup
                                     let _ = tx
                                         .send(FfmpegEvent::Error {
                                             id: job_id.clone(),
                                             message: build_error_message(
                                                 status.code().unwrap_or(-1),
-                                                &log_tail,
+                                                &log_tail.lock().await,
                                             ),
                                         })
                                         .await;
@@ -418,6 +967,12 @@ pub async fn run_ffmpeg(
                                 return Ok(());
                             }
 
+                            // Fortschritt beobachtet – Watchdog zuruecksetzen.
+                            if progress.frame > last_frame {
+                                last_frame = progress.frame;
+                                watchdog.as_mut().reset(tokio::time::Instant::now() + watchdog_duration);
+                            }
+
                             let percent = calculate_progress(
                                 progress.out_time_us,
                                 total_duration_us,
@@ -430,32 +985,33 @@ pub async fn run_ffmpeg(
                                     fps: progress.fps,
                                     speed: progress.speed,
                                     frame: progress.frame,
+                                    elapsed_us: progress.out_time_us,
                                 })
                                 .await;
                         } else {
                             // Keine Progress-Zeile → FFmpeg-Lognachricht puffern
-                            if log_tail.len() == 20 {
-                                log_tail.remove(0);
+                            let mut tail = log_tail.lock().await;
+                            if tail.len() == 20 {
+                                tail.remove(0);
                             }
-                            log_tail.push(line);
+                            tail.push(line);
                         }
                     }
                     Ok(None) => {
-                        // stderr geschlossen – Prozess beendet
+                        // Progress-Stream geschlossen (stderr oder TCP-Socket) – Prozess beendet
                         let status = child.wait().await?;
                         pid_slot.store(0, Ordering::Release);
                         if status.success() {
-                            let _ = tx
-                                .send(FfmpegEvent::Done { id: job_id.clone() })
-                                .await;
+                            finish_success(&tx, &job_id, sink_handle.take()).await;
                         } else {
+                            if let Some(h) = sink_handle.take() { h.abort(); }
                             let _ = std::fs::remove_file(output_path); // partial file cleanup
                             let _ = tx
                                 .send(FfmpegEvent::Error {
                                     id: job_id.clone(),
                                     message: build_error_message(
                                         status.code().unwrap_or(-1),
-                                        &log_tail,
+                                        &log_tail.lock().await,
                                     ),
                                 })
                                 .await;
@@ -466,11 +1022,12 @@ pub async fn run_ffmpeg(
                         let _ = child.kill().await;
                         let _ = child.wait().await;  // Zombie verhindern
                         pid_slot.store(0, Ordering::Release);
+                        if let Some(h) = sink_handle.take() { h.abort(); }
                         let _ = std::fs::remove_file(output_path); // partial file cleanup
                         let _ = tx
                             .send(FfmpegEvent::Error {
                                 id: job_id.clone(),
-                                message: format!("Fehler beim Lesen von stderr: {e}"),
+                                message: format!("Fehler beim Lesen des Progress-Streams: {e}"),
                             })
                             .await;
                         return Ok(());
@@ -481,8 +1038,162 @@ pub async fn run_ffmpeg(
     }
 }
 
-/// Baut eine lesbare Fehlermeldung mit FFmpeg-Logausgabe.
-fn build_error_message(exit_code: i32, log_tail: &[String]) -> String {
+/// Fuehrt einen klassischen Zwei-Pass-Encode durch (`RateControl::TwoPass`, siehe
+/// `jobs::transcode::dispatch_job`): Pass 1 analysiert den Inhalt (Output verworfen
+/// ueber den Null-Muxer) und schreibt Bitrate-Statistiken in eine `-passlogfile`,
+/// Pass 2 nutzt diese Statistiken fuer die eigentliche Bitrateverteilung. Beide
+/// Passes laufen ueber den normalen `run_ffmpeg` (Watchdog/Cancel-Handling bleibt
+/// erhalten); ihre Events werden ueber je einen Zwischenkanal abgefangen, auf die
+/// jeweilige Haelfte der Gesamt-Prozentanzeige skaliert (Pass 1 → 0-50%, Pass 2 →
+/// 50-100%) und gemeinsam ueber `tx` weitergereicht, damit der Job von aussen wie
+/// ein einzelner Lauf mit durchgehendem Fortschritt erscheint.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_two_pass_ffmpeg(
+    job_id: String,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    mode: JobMode,
+    options: JobOptions,
+    nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    color: ColorMetadata,
+    total_duration_us: i64,
+    process_timeout_secs: u64,
+    progress_via_tcp: bool,
+    tx: mpsc::Sender<FfmpegEvent>,
+    cancel: CancellationToken,
+    pid_slot: Arc<AtomicU32>,
+    paused_rx: watch::Receiver<bool>,
+) -> Result<()> {
+    let passlog_path = std::env::temp_dir().join(format!("proxy-gen-2pass-{job_id}"));
+    let network_sink = network_sink::parse_output_url(&options)?;
+
+    // ── Pass 1: Analyse, Output nach /dev/null via Null-Muxer ──────────────────
+    let pass1_args = build_pass_args(
+        build_ffmpeg_args(&input_path, &output_path, &mode, &options, nvenc_full_gpu, crf_override, &color),
+        1,
+        &passlog_path,
+    );
+    let (pass1_tx, mut pass1_rx) = mpsc::channel::<FfmpegEvent>(64);
+    let pass1_handle = {
+        let job_id = job_id.clone();
+        let cancel = cancel.clone();
+        let pid_slot = pid_slot.clone();
+        let paused_rx = paused_rx.clone();
+        tokio::spawn(async move {
+            run_ffmpeg(
+                job_id,
+                pass1_args,
+                Path::new("/dev/null"),
+                total_duration_us,
+                process_timeout_secs,
+                progress_via_tcp,
+                // Pass 1 verwirft seinen Output ueber den Null-Muxer, ein
+                // Netzwerk-Sink ergibt hier keinen Sinn (siehe Pass 2 unten).
+                None,
+                pass1_tx,
+                cancel,
+                pid_slot,
+                paused_rx,
+            )
+            .await
+        })
+    };
+
+    while let Some(event) = pass1_rx.recv().await {
+        match event {
+            FfmpegEvent::Progress { id, percent, fps, speed, frame, .. } => {
+                let _ = tx
+                    .send(FfmpegEvent::Progress { id, percent: percent * 0.5, fps, speed, frame, elapsed_us: 0 })
+                    .await;
+            }
+            FfmpegEvent::Cancelled { id } => {
+                cleanup_passlog(&passlog_path);
+                let _ = tx.send(FfmpegEvent::Cancelled { id }).await;
+                return Ok(());
+            }
+            FfmpegEvent::Error { id, message } => {
+                cleanup_passlog(&passlog_path);
+                let _ = tx
+                    .send(FfmpegEvent::Error { id, message: format!("Zwei-Pass (Pass 1): {message}") })
+                    .await;
+                return Ok(());
+            }
+            FfmpegEvent::Done { .. } | FfmpegEvent::Uploaded { .. } | FfmpegEvent::SegmentReady { .. } => {}
+        }
+    }
+    pass1_handle.await.context("Zwei-Pass (Pass 1) Task Panik")??;
+
+    // ── Pass 2: eigentlicher Encode mit den Statistiken aus Pass 1 ─────────────
+    let pass2_args = build_pass_args(
+        build_ffmpeg_args(&input_path, &output_path, &mode, &options, nvenc_full_gpu, crf_override, &color),
+        2,
+        &passlog_path,
+    );
+    let (pass2_tx, mut pass2_rx) = mpsc::channel::<FfmpegEvent>(64);
+    let pass2_handle = tokio::spawn(async move {
+        run_ffmpeg(
+            job_id,
+            pass2_args,
+            &output_path,
+            total_duration_us,
+            process_timeout_secs,
+            progress_via_tcp,
+            network_sink,
+            pass2_tx,
+            cancel,
+            pid_slot,
+            paused_rx,
+        )
+        .await
+    });
+
+    while let Some(event) = pass2_rx.recv().await {
+        match event {
+            FfmpegEvent::Progress { id, percent, fps, speed, frame, elapsed_us } => {
+                let _ = tx
+                    .send(FfmpegEvent::Progress { id, percent: 50.0 + percent * 0.5, fps, speed, frame, elapsed_us })
+                    .await;
+            }
+            other => {
+                let _ = tx.send(other).await;
+            }
+        }
+    }
+    let result = pass2_handle.await.context("Zwei-Pass (Pass 2) Task Panik")?;
+
+    cleanup_passlog(&passlog_path);
+    result
+}
+
+/// Haengt `-pass N`/`-passlogfile` an eine von `build_ffmpeg_args` gebaute
+/// Argumentliste an. Pass 1 verwirft den eigentlichen Output zugunsten des
+/// Null-Muxers (`-f null /dev/null`), Pass 2 behaelt den urspruenglichen Output.
+fn build_pass_args(mut args: Vec<String>, pass: u8, passlog_path: &Path) -> Vec<String> {
+    let output = args.pop(); // letztes Element ist immer der Output-Pfad (siehe build_ffmpeg_args)
+    args.push("-pass".to_string());
+    args.push(pass.to_string());
+    args.push("-passlogfile".to_string());
+    args.push(passlog_path.to_string_lossy().to_string());
+    if pass == 1 {
+        args.push("-f".to_string());
+        args.push("null".to_string());
+        args.push("/dev/null".to_string());
+    } else if let Some(output) = output {
+        args.push(output);
+    }
+    args
+}
+
+/// Raeumt die x264/x265-Statistikdateien eines Zwei-Pass-Laufs auf.
+fn cleanup_passlog(passlog_path: &Path) {
+    let _ = std::fs::remove_file(format!("{}-0.log", passlog_path.display()));
+    let _ = std::fs::remove_file(format!("{}-0.log.mbtree", passlog_path.display()));
+}
+
+/// Baut eine lesbare Fehlermeldung mit FFmpeg-Logausgabe. Wird auch von anderen
+/// FFmpeg-Prozess-Wrappern (z.B. `ffmpeg::concat`) wiederverwendet.
+pub(crate) fn build_error_message(exit_code: i32, log_tail: &[String]) -> String {
     if log_tail.is_empty() {
         return format!("FFmpeg beendet mit Exit-Code: {exit_code}");
     }
@@ -0,0 +1,318 @@
+// Fragmented-MP4/HLS-Segment-Stream (`JobMode::Stream`): statt einer
+// monolithischen Output-Datei erzeugt FFmpegs eigener HLS-Muxer eine Playlist
+// (`playlist.m3u8`), bei `stream_format == "fmp4"` ein Init-Segment (`init.mp4`,
+// enthaelt `moov`) und fortlaufend nummerierte Media-Segmente. `-hls_playlist_type
+// event` haengt Segmente an eine bereits gueltige Playlist an statt sie erst am
+// Ende zu schreiben – ein Consumer kann also schon frueh verfuegbare Segmente
+// ausliefern, waehrend FFmpeg noch an spaeteren arbeitet. Segment-Fertigstellung
+// wird per Polling der Playlist-Datei erkannt (FFmpeg selbst meldet neue Segmente
+// nicht ueber `-progress`) und als `FfmpegEvent::SegmentReady` weitergereicht.
+//
+// `fragment_duration_secs` (`-hls_time`) bestimmt die Segmentlaenge (Seek-
+// Granularitaet der Playlist), `chunk_duration_secs` (`-frag_duration`, nur
+// `fmp4`) die Sub-Fragmentlaenge *innerhalb* jedes Segments fuer latenzarme
+// Auslieferung – ein Client kann so mit dem ersten Fragment eines Segments
+// beginnen, statt auf das komplette Segment warten zu muessen.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::ffmpeg::progress::{calculate_progress, ProgressParser};
+use crate::ffmpeg::runner::{
+    build_error_message, push_duration_limit_args, push_hwaccel_prefix, push_live_source_input_args,
+    push_proxy_codec_args, ColorMetadata, FfmpegEvent,
+};
+use crate::ipc::protocol::JobOptions;
+
+/// Baut die FFmpeg-Argumente fuer den Segment-Stream sowie den Pfad der Playlist.
+/// Wiederverwendet dieselbe Codec-Logik wie der normale Proxy-Pfad
+/// (`push_proxy_codec_args`), damit Segment-Streams dieselben Encoder-Optionen
+/// (HW-Accel, Aufloesung, CRF-Override, Tonemap) unterstuetzen.
+pub fn build_segment_args(
+    input_path: &Path,
+    stream_dir: &Path,
+    options: &JobOptions,
+    nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    color: &ColorMetadata,
+) -> (Vec<String>, PathBuf) {
+    let is_fmp4 = options.stream_format != "hls";
+    let segment_ext = if is_fmp4 { "m4s" } else { "ts" };
+    let playlist_path = stream_dir.join("playlist.m3u8");
+    let segment_pattern = stream_dir.join(format!("segment_%05d.{segment_ext}"));
+
+    let mut args = Vec::new();
+    args.push("-y".to_string());
+
+    if !crate::ffmpeg::runner::is_prores(&options.proxy_codec) {
+        push_hwaccel_prefix(&mut args, &options.hw_accel, nvenc_full_gpu);
+    }
+
+    args.push("-loglevel".to_string());
+    args.push("warning".to_string());
+
+    // Live-Quelle (RTSP/V4L2, siehe probe::classify_live_source): Transport-/
+    // Geraete-Flags muessen VOR -i stehen, ohne Effekt bei Datei-Inputs.
+    push_live_source_input_args(&mut args, input_path, options);
+
+    args.push("-i".to_string());
+    args.push(input_path.to_string_lossy().to_string());
+
+    args.push("-map".to_string());
+    args.push("0:v:0".to_string());
+    args.push("-map".to_string());
+    args.push("0:a".to_string());
+
+    let res = options.proxy_resolution.as_deref().map(|r| r.replace('x', ":"));
+    push_proxy_codec_args(&mut args, &options.proxy_codec, &options.hw_accel, res.as_deref(), nvenc_full_gpu, crf_override, options.rate_control.as_ref(), color);
+
+    args.push("-c:a".to_string());
+    args.push("aac".to_string());
+
+    args.push("-f".to_string());
+    args.push("hls".to_string());
+    args.push("-hls_time".to_string());
+    args.push(options.fragment_duration_secs.to_string());
+    // "event": Playlist ist nach jedem Segment sofort gueltig abspielbar statt
+    // erst nach Prozessende (wie bei "vod") geschrieben zu werden – Grundlage
+    // dafuer, dass ein Consumer frueh verfuegbare Segmente ausliefern kann.
+    args.push("-hls_playlist_type".to_string());
+    args.push("event".to_string());
+    args.push("-hls_flags".to_string());
+    args.push("independent_segments+append_list".to_string());
+    args.push("-hls_segment_filename".to_string());
+    args.push(segment_pattern.to_string_lossy().to_string());
+
+    if is_fmp4 {
+        args.push("-hls_segment_type".to_string());
+        args.push("fmp4".to_string());
+        args.push("-hls_fmp4_init_filename".to_string());
+        args.push("init.mp4".to_string());
+        // Sub-Fragmentierung innerhalb jedes Segments fuer latenzarme Auslieferung
+        // (ffmpeg erwartet `-frag_duration` in Mikrosekunden).
+        let frag_duration_us = (options.chunk_duration_secs * 1_000_000.0).max(0.0) as i64;
+        args.push("-frag_duration".to_string());
+        args.push(frag_duration_us.to_string());
+    }
+
+    // Wall-Clock-/Byte-Limit fuer Quellen ohne bekannte Gesamtdauer (siehe
+    // `JobOptions::max_duration_secs`/`max_bytes`), ohne Effekt wenn nicht gesetzt.
+    push_duration_limit_args(&mut args, options);
+
+    args.push("-progress".to_string());
+    args.push("pipe:2".to_string());
+
+    args.push(playlist_path.to_string_lossy().to_string());
+
+    (args, playlist_path)
+}
+
+/// Liest neu hinzugekommene Segment-Zeilen aus einer HLS-Playlist (alles was
+/// nicht mit `#` beginnt) und gibt sie – relativ zum Playlist-Verzeichnis
+/// aufgeloest – in Reihenfolge zurueck. `seen` wird dabei um die neuen
+/// Dateinamen erweitert, damit ein zweiter Aufruf sie nicht erneut meldet.
+fn scan_new_segments(playlist_contents: &str, stream_dir: &Path, seen: &mut HashSet<String>) -> Vec<String> {
+    let mut new_segments = Vec::new();
+    for line in playlist_contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if seen.insert(line.to_string()) {
+            new_segments.push(stream_dir.join(line).to_string_lossy().to_string());
+        }
+    }
+    new_segments
+}
+
+/// Fuehrt einen Segment-Stream-Job durch: baut die Argumente, startet FFmpeg
+/// und meldet sowohl `Progress` (aus `-progress`) als auch `SegmentReady`
+/// (aus periodischem Polling der Playlist) ueber den Event-Channel.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_segmented_job(
+    job_id: String,
+    input_path: PathBuf,
+    stream_dir: PathBuf,
+    options: JobOptions,
+    nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    color: ColorMetadata,
+    total_duration_us: i64,
+    tx: mpsc::Sender<FfmpegEvent>,
+    cancel: CancellationToken,
+    pid_slot: Arc<AtomicU32>,
+) -> Result<()> {
+    tokio::fs::create_dir_all(&stream_dir)
+        .await
+        .with_context(|| format!("Konnte Stream-Verzeichnis nicht anlegen: {}", stream_dir.display()))?;
+
+    let (args, playlist_path) = build_segment_args(&input_path, &stream_dir, &options, nvenc_full_gpu, crf_override, &color);
+
+    let mut child = tokio::process::Command::new("ffmpeg")
+        .args(&args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("FFmpeg (Segment-Stream) konnte nicht gestartet werden")?;
+
+    pid_slot.store(child.id().unwrap_or(0), Ordering::Release);
+
+    let stderr = child.stderr.take().context("Konnte stderr von FFmpeg nicht lesen")?;
+    let mut stdin = child.stdin.take();
+
+    let mut reader = BufReader::new(stderr).lines();
+    let mut parser = ProgressParser::new();
+    let mut log_tail: Vec<String> = Vec::with_capacity(20);
+
+    let mut seen_segments: HashSet<String> = HashSet::new();
+    let mut next_index: u32 = 0;
+    let mut segment_poll = tokio::time::interval(tokio::time::Duration::from_millis(500));
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                if let Some(ref mut stdin_handle) = stdin {
+                    let _ = stdin_handle.write_all(b"q\n").await;
+                    let _ = stdin_handle.flush().await;
+                }
+                let _ = child.wait().await;
+                pid_slot.store(0, Ordering::Release);
+                let _ = tx.send(FfmpegEvent::Cancelled { id: job_id.clone() }).await;
+                return Ok(());
+            }
+            _ = segment_poll.tick() => {
+                if let Ok(contents) = tokio::fs::read_to_string(&playlist_path).await {
+                    for segment_path in scan_new_segments(&contents, &stream_dir, &mut seen_segments) {
+                        let _ = tx
+                            .send(FfmpegEvent::SegmentReady {
+                                id: job_id.clone(),
+                                path: segment_path,
+                                index: next_index,
+                            })
+                            .await;
+                        next_index += 1;
+                    }
+                }
+            }
+            line = reader.next_line() => {
+                match line {
+                    Ok(Some(line)) => {
+                        if let Some(progress) = parser.feed_line(&line) {
+                            if progress.is_done {
+                                let status = child.wait().await?;
+                                pid_slot.store(0, Ordering::Release);
+                                // Letzter Scan: Segmente die erst beim Exit geschrieben wurden.
+                                if let Ok(contents) = tokio::fs::read_to_string(&playlist_path).await {
+                                    for segment_path in scan_new_segments(&contents, &stream_dir, &mut seen_segments) {
+                                        let _ = tx
+                                            .send(FfmpegEvent::SegmentReady {
+                                                id: job_id.clone(),
+                                                path: segment_path,
+                                                index: next_index,
+                                            })
+                                            .await;
+                                        next_index += 1;
+                                    }
+                                }
+                                if status.success() {
+                                    let _ = tx.send(FfmpegEvent::Done { id: job_id.clone() }).await;
+                                } else {
+                                    let _ = tokio::fs::remove_dir_all(&stream_dir).await; // partial output cleanup
+                                    let _ = tx
+                                        .send(FfmpegEvent::Error {
+                                            id: job_id.clone(),
+                                            message: build_error_message(status.code().unwrap_or(-1), &log_tail),
+                                        })
+                                        .await;
+                                }
+                                return Ok(());
+                            }
+
+                            let percent = calculate_progress(progress.out_time_us, total_duration_us);
+                            let _ = tx
+                                .send(FfmpegEvent::Progress {
+                                    id: job_id.clone(),
+                                    percent: percent * 100.0,
+                                    fps: progress.fps,
+                                    speed: progress.speed,
+                                    frame: progress.frame,
+                                    elapsed_us: progress.out_time_us,
+                                })
+                                .await;
+                        } else {
+                            if log_tail.len() == 20 {
+                                log_tail.remove(0);
+                            }
+                            log_tail.push(line);
+                        }
+                    }
+                    Ok(None) => {
+                        let status = child.wait().await?;
+                        pid_slot.store(0, Ordering::Release);
+                        if status.success() {
+                            let _ = tx.send(FfmpegEvent::Done { id: job_id.clone() }).await;
+                        } else {
+                            let _ = tokio::fs::remove_dir_all(&stream_dir).await; // partial output cleanup
+                            let _ = tx
+                                .send(FfmpegEvent::Error {
+                                    id: job_id.clone(),
+                                    message: build_error_message(status.code().unwrap_or(-1), &log_tail),
+                                })
+                                .await;
+                        }
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        let _ = child.kill().await;
+                        let _ = child.wait().await; // Zombie verhindern
+                        pid_slot.store(0, Ordering::Release);
+                        let _ = tokio::fs::remove_dir_all(&stream_dir).await; // partial output cleanup
+                        let _ = tx
+                            .send(FfmpegEvent::Error {
+                                id: job_id.clone(),
+                                message: format!("Fehler beim Lesen von stderr: {e}"),
+                            })
+                            .await;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_new_segments_finds_only_unseen_entries() {
+        let stream_dir = PathBuf::from("/tmp/stream-job");
+        let mut seen = HashSet::new();
+
+        let first_poll = "#EXTM3U\n#EXT-X-VERSION:7\nsegment_00000.m4s\n";
+        let found = scan_new_segments(first_poll, &stream_dir, &mut seen);
+        assert_eq!(found, vec![stream_dir.join("segment_00000.m4s").to_string_lossy().to_string()]);
+
+        // Zweiter Poll: dieselbe Zeile nochmal (noch nicht rotiert) plus eine neue.
+        let second_poll = "#EXTM3U\nsegment_00000.m4s\nsegment_00001.m4s\n";
+        let found = scan_new_segments(second_poll, &stream_dir, &mut seen);
+        assert_eq!(found, vec![stream_dir.join("segment_00001.m4s").to_string_lossy().to_string()]);
+    }
+
+    #[test]
+    fn scan_new_segments_ignores_playlist_directives() {
+        let stream_dir = PathBuf::from("/tmp/stream-job");
+        let mut seen = HashSet::new();
+        let playlist = "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXT-X-PLAYLIST-TYPE:EVENT\n";
+        assert!(scan_new_segments(playlist, &stream_dir, &mut seen).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+// Target-Quality-Modus: statt einer festen CRF/QP gibt der Nutzer einen Ziel-VMAF-
+// Score vor (siehe `JobOptions::target_vmaf`). `converge_crf` schneidet mehrere ueber
+// die Laufzeit verteilte, kurze Ausschnitte aus der Quelle (siehe
+// `cut_reference_sample`), kodiert sie bei wechselnden CRF/QP-Werten und scort jede
+// Probe per `libvmaf`-Filter gegen die Ausschnitte, bis der Score innerhalb der
+// Toleranz liegt.
+//
+// Die Kandidatensuche selbst (`predict_crf`: Sekanten- bzw. quadratische
+// Interpolation; das CRF-Intervall wird nicht halbiert) kennt nur Zahlenwerte, keine
+// Codec-Semantik – sie funktioniert fuer SW-CRF (libx264/libx265/libsvtav1, faellt
+// monoton mit steigendem Wert) genauso wie fuer VAAPI/NVENC-QP (steigender QP senkt
+// die Qualitaet analog zu steigender CRF). `score_candidate` reicht dafuer das
+// Job-`hw_accel` an `push_proxy_codec_args` durch (siehe dort fuer den
+// `RateControl::ConstQp`-Pfad), probt also mit demselben Encoder-Backend, das der
+// eigentliche Job spaeter benutzt – nicht mehr zwangsweise auf der CPU.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::ffmpeg::runner::push_proxy_codec_args;
+
+/// Ergebnis einer einzelnen Probe: CRF-Wert und gemessener VMAF-Score.
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    crf: u32,
+    vmaf: f64,
+}
+
+/// Konvergiert per Probing auf den CRF/QP-Wert, der `target_vmaf` am naechsten kommt
+/// (`hw_accel` waehlt das Encoder-Backend fuer die Probe-Encodes, siehe
+/// `score_candidate` – bei `"vaapi"`/`"nvenc"` ist das Ergebnis ein QP, sonst eine
+/// CRF, siehe `ffmpeg::runner::push_proxy_codec_args`). Startpunkte sind
+/// `crf_min`/`crf_max` (bei Hardware-Encodern entsprechend als QP-Grenzen zu
+/// verstehen – je nach Encoder/Codec ist der sinnvolle Bereich ein anderer als bei
+/// SW-CRF, `JobOptions::vmaf_crf_min`/`vmaf_crf_max` muessen dafuer passend gesetzt
+/// werden); danach liefert Sekanten- (zwei Punkte) bzw. quadratische Interpolation
+/// (ab drei Punkten) den naechsten Kandidaten, geklammert auf `[crf_min, crf_max]`.
+/// Bricht ab sobald eine Probe innerhalb von `tolerance` VMAF-Punkten liegt oder
+/// `max_probes` erreicht ist; liefert dann den bisher besten Treffer. Doppelte
+/// Kandidaten werden nicht erneut kodiert.
+#[allow(clippy::too_many_arguments)]
+pub async fn converge_crf(
+    job_id: &str,
+    input_path: &Path,
+    total_duration_us: i64,
+    proxy_codec: &str,
+    hw_accel: &str,
+    resolution: Option<&str>,
+    target_vmaf: f32,
+    crf_min: u32,
+    crf_max: u32,
+    tolerance: f32,
+    max_probes: u32,
+) -> Result<u32> {
+    let tmp_dir = std::env::temp_dir().join(format!("proxy-gen-vmaf-{job_id}"));
+    tokio::fs::create_dir_all(&tmp_dir)
+        .await
+        .context("VMAF-Tempverzeichnis konnte nicht angelegt werden")?;
+
+    let reference = tmp_dir.join("reference.mov");
+    let cut_result = cut_reference_sample(&tmp_dir, input_path, &reference, total_duration_us).await;
+    if let Err(e) = cut_result {
+        let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+        return Err(e);
+    }
+
+    let mut probes: Vec<Probe> = Vec::new();
+    let mut scored: HashMap<u32, f64> = HashMap::new();
+    let mut queue: Vec<u32> = vec![crf_min, crf_max];
+
+    while !queue.is_empty() && (probes.len() as u32) < max_probes.max(1) {
+        let crf = queue.remove(0);
+        if scored.contains_key(&crf) {
+            continue;
+        }
+
+        let vmaf = match score_candidate(&tmp_dir, &reference, proxy_codec, hw_accel, resolution, crf).await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("VMAF-Probe bei CRF {crf} fehlgeschlagen: {e}");
+                continue;
+            }
+        };
+        scored.insert(crf, vmaf);
+        probes.push(Probe { crf, vmaf });
+
+        if (vmaf - target_vmaf as f64).abs() <= tolerance as f64 {
+            break;
+        }
+
+        if queue.is_empty() && (probes.len() as u32) < max_probes.max(1) {
+            let next = predict_crf(&probes, target_vmaf as f64, crf_min, crf_max);
+            if !scored.contains_key(&next) {
+                queue.push(next);
+            }
+        }
+    }
+
+    let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+
+    probes
+        .into_iter()
+        .min_by(|a, b| {
+            (a.vmaf - target_vmaf as f64)
+                .abs()
+                .partial_cmp(&(b.vmaf - target_vmaf as f64).abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|p| p.crf)
+        .context("Keine VMAF-Probe war erfolgreich")
+}
+
+/// Anzahl und Laenge der ueber die Laufzeit verteilten Probefenster (siehe
+/// `cut_reference_sample`).
+const PROBE_WINDOWS: usize = 3;
+const PROBE_WINDOW_SECS: f64 = 2.0;
+
+/// Schneidet `PROBE_WINDOWS` gleichmaessig ueber die Laufzeit verteilte, je
+/// `PROBE_WINDOW_SECS` lange Ausschnitte verlustfrei per Stream-Copy aus der Quelle
+/// und fuegt sie per Concat-Demuxer zu einer einzigen Referenzdatei zusammen. Mehrere
+/// ueber die Quelle verstreute Fenster sind repraesentativer fuer stark wechselndes
+/// Material als ein einzelner Ausschnitt. Probe-Encodes laufen gegen diese
+/// Referenzdatei statt gegen die Quelle selbst, damit Referenz und Probe garantiert
+/// dieselbe Framezahl/Timing haben (kein Alignment-Problem bei der VMAF-Messung).
+async fn cut_reference_sample(
+    tmp_dir: &Path,
+    input_path: &Path,
+    reference_path: &Path,
+    total_duration_us: i64,
+) -> Result<()> {
+    let duration_secs = (total_duration_us.max(0) as f64) / 1_000_000.0;
+    let window_len = duration_secs.max(1.0).min(PROBE_WINDOW_SECS);
+
+    let mut window_paths = Vec::with_capacity(PROBE_WINDOWS);
+    for i in 0..PROBE_WINDOWS {
+        // Fenster gleichmaessig zwischen 10% und 90% der Laufzeit verteilen, damit
+        // weder Intro- noch Outro-Frames (oft untypisch fuers restliche Material)
+        // die Messung dominieren.
+        let fraction = 0.1 + 0.8 * (i as f64 / (PROBE_WINDOWS.max(2) - 1) as f64);
+        let start_secs = (duration_secs * fraction).max(0.0);
+        let window_path = tmp_dir.join(format!("window-{i}.mov"));
+
+        let status = tokio::process::Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "warning"])
+            .arg("-ss")
+            .arg(format!("{start_secs:.3}"))
+            .arg("-i")
+            .arg(input_path.as_os_str())
+            .arg("-t")
+            .arg(format!("{window_len:.3}"))
+            .args(["-map", "0:v:0", "-an", "-c:v", "copy"])
+            .arg(&window_path)
+            .status()
+            .await
+            .context("FFmpeg (VMAF-Probefenster) konnte nicht gestartet werden")?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "FFmpeg (VMAF-Probefenster) beendet mit Exit-Code: {}",
+                status.code().unwrap_or(-1)
+            ));
+        }
+        window_paths.push(window_path);
+    }
+
+    let list_path = tmp_dir.join("windows.txt");
+    let list_content: String = window_paths
+        .iter()
+        .map(|p| format!("file '{}'\n", p.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    tokio::fs::write(&list_path, list_content)
+        .await
+        .context("Concat-Liste fuer VMAF-Probefenster konnte nicht geschrieben werden")?;
+
+    let concat_status = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "warning", "-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        .args(["-c", "copy"])
+        .arg(reference_path)
+        .status()
+        .await
+        .context("FFmpeg (Concat der VMAF-Probefenster) konnte nicht gestartet werden")?;
+
+    for window_path in &window_paths {
+        let _ = tokio::fs::remove_file(window_path).await;
+    }
+    let _ = tokio::fs::remove_file(&list_path).await;
+
+    if !concat_status.success() {
+        return Err(anyhow::anyhow!(
+            "FFmpeg (Concat der VMAF-Probefenster) beendet mit Exit-Code: {}",
+            concat_status.code().unwrap_or(-1)
+        ));
+    }
+    Ok(())
+}
+
+/// Kodiert `reference` bei `crf` (CRF fuer SW-Encoder, QP bei `hw_accel` ==
+/// `"vaapi"`/`"nvenc"`, siehe `push_proxy_codec_args`) und misst den VMAF-Score des
+/// Ergebnisses gegen `reference` per `libvmaf`-Filter. Raeumt Probe-Encode und
+/// VMAF-Log danach auf.
+async fn score_candidate(
+    tmp_dir: &Path,
+    reference: &Path,
+    proxy_codec: &str,
+    hw_accel: &str,
+    resolution: Option<&str>,
+    crf: u32,
+) -> Result<f64> {
+    let distorted: PathBuf = tmp_dir.join(format!("probe-{crf}.mp4"));
+
+    let mut args = vec!["-y".to_string(), "-loglevel".to_string(), "warning".to_string()];
+    // Device-Init fuer VAAPI/NVENC (`hwupload` braucht sie), wie im Produktions-Encode.
+    crate::ffmpeg::runner::push_hwaccel_prefix(&mut args, hw_accel, false);
+    args.push("-i".to_string());
+    args.push(reference.to_string_lossy().to_string());
+    // Probe-Encode laeuft ueber dasselbe Encoder-Backend wie der spaetere Job
+    // (siehe `hw_accel`), damit der konvergierte CRF/QP-Wert tatsaechlich zum
+    // Produktions-Encode passt. Kein Full-GPU-Pipeline-Decode (`nvenc_full_gpu =
+    // false`): die Referenz wird per CPU dekodiert und nur fuer NVENC per
+    // `hwupload` hochgeladen (siehe `push_nvenc`), das genuegt fuer eine kurze
+    // Probe und spart den (hier nicht vorhandenen) CUDA-Decode-Pfad.
+    // Farbmetadaten sind fuer die Probe irrelevant (wird danach verworfen) –
+    // Default-`ColorMetadata` wie bei BRAW/R3D-Bridge.
+    push_proxy_codec_args(
+        &mut args,
+        proxy_codec,
+        hw_accel,
+        resolution,
+        false,
+        Some(crf),
+        None,
+        &crate::ffmpeg::runner::ColorMetadata::default(),
+    );
+    args.push(distorted.to_string_lossy().to_string());
+
+    let encode_status = tokio::process::Command::new("ffmpeg")
+        .args(&args)
+        .status()
+        .await
+        .context("FFmpeg (VMAF-Probe-Encode) konnte nicht gestartet werden")?;
+    if !encode_status.success() {
+        return Err(anyhow::anyhow!(
+            "FFmpeg (VMAF-Probe-Encode) beendet mit Exit-Code: {}",
+            encode_status.code().unwrap_or(-1)
+        ));
+    }
+
+    let log_path = tmp_dir.join(format!("vmaf-{crf}.json"));
+    let filter = format!("libvmaf=log_fmt=json:log_path={}", log_path.to_string_lossy());
+    let vmaf_status = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "warning"])
+        .arg("-i")
+        .arg(&distorted)
+        .arg("-i")
+        .arg(reference)
+        .args(["-lavfi", &filter, "-f", "null", "-"])
+        .status()
+        .await
+        .context("FFmpeg (libvmaf) konnte nicht gestartet werden")?;
+
+    let _ = tokio::fs::remove_file(&distorted).await;
+
+    if !vmaf_status.success() {
+        let _ = tokio::fs::remove_file(&log_path).await;
+        return Err(anyhow::anyhow!(
+            "FFmpeg (libvmaf) beendet mit Exit-Code: {}",
+            vmaf_status.code().unwrap_or(-1)
+        ));
+    }
+
+    let log = tokio::fs::read_to_string(&log_path)
+        .await
+        .context("VMAF-Log konnte nicht gelesen werden")?;
+    let _ = tokio::fs::remove_file(&log_path).await;
+
+    let v: Value = serde_json::from_str(&log).context("VMAF-Log (JSON) ungueltig")?;
+    v["pooled_metrics"]["vmaf"]["mean"]
+        .as_f64()
+        .context("VMAF-Score nicht im Log gefunden")
+}
+
+/// Sagt den naechsten CRF-Kandidaten voraus: Sekante durch die beiden bisherigen
+/// Punkte, ab drei Punkten quadratische Lagrange-Interpolation (CRF als Funktion
+/// von VMAF). VMAF faellt monoton mit steigendem CRF, daher ist die Interpolation
+/// wohldefiniert solange die Probe-Punkte nicht denselben Score liefern.
+fn predict_crf(probes: &[Probe], target_vmaf: f64, crf_min: u32, crf_max: u32) -> u32 {
+    let mut sorted = probes.to_vec();
+    sorted.sort_by(|a, b| a.crf.cmp(&b.crf));
+
+    let predicted = match sorted.len() {
+        0 | 1 => (crf_min as f64 + crf_max as f64) / 2.0,
+        2 => secant(&sorted[0], &sorted[1], target_vmaf),
+        _ => {
+            let n = sorted.len();
+            quadratic_fit(&sorted[n - 3..], target_vmaf)
+                .unwrap_or_else(|| secant(&sorted[n - 2], &sorted[n - 1], target_vmaf))
+        }
+    };
+
+    predicted.round().clamp(crf_min as f64, crf_max as f64) as u32
+}
+
+fn secant(a: &Probe, b: &Probe, target_vmaf: f64) -> f64 {
+    if (a.vmaf - b.vmaf).abs() < f64::EPSILON {
+        return (a.crf + b.crf) as f64 / 2.0;
+    }
+    a.crf as f64 + (target_vmaf - a.vmaf) * (b.crf as f64 - a.crf as f64) / (b.vmaf - a.vmaf)
+}
+
+/// Lagrange-Quadratik durch drei Punkte `(vmaf, crf)`, ausgewertet bei `target_vmaf`.
+/// `None` falls zwei Punkte denselben VMAF-Score haben (Division durch Null).
+fn quadratic_fit(pts: &[Probe], target_vmaf: f64) -> Option<f64> {
+    let (x0, y0) = (pts[0].vmaf, pts[0].crf as f64);
+    let (x1, y1) = (pts[1].vmaf, pts[1].crf as f64);
+    let (x2, y2) = (pts[2].vmaf, pts[2].crf as f64);
+
+    let d0 = (x0 - x1) * (x0 - x2);
+    let d1 = (x1 - x0) * (x1 - x2);
+    let d2 = (x2 - x0) * (x2 - x1);
+    if d0.abs() < f64::EPSILON || d1.abs() < f64::EPSILON || d2.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let l0 = (target_vmaf - x1) * (target_vmaf - x2) / d0;
+    let l1 = (target_vmaf - x0) * (target_vmaf - x2) / d1;
+    let l2 = (target_vmaf - x0) * (target_vmaf - x1) / d2;
+    Some(y0 * l0 + y1 * l1 + y2 * l2)
+}
@@ -29,6 +29,30 @@ pub enum Request {
 
     #[serde(rename = "shutdown")]
     Shutdown,
+
+    /// Kooperatives Drain: keine neuen Jobs mehr annehmen, wartende Jobs abbrechen,
+    /// laufende Jobs aber zu Ende laufen lassen (siehe `Response::DrainStatus`).
+    /// Ein hartes `shutdown` bleibt als separate Force-Quit-Eskalation verfuegbar.
+    #[serde(rename = "drain")]
+    Drain,
+
+    /// Setzt die Dispatch-Prioritaet eines noch wartenden Jobs neu (siehe
+    /// `JobOptions::priority`). Ohne Effekt falls der Job bereits laeuft oder
+    /// nicht mehr existiert.
+    #[serde(rename = "set_priority")]
+    SetPriority { id: String, priority: u8 },
+
+    /// Pausiert einen laufenden Job per `SIGSTOP` an seinen getrackten PIDs
+    /// (siehe `PidSet`, `pid_slot`-Kommentare in `braw::runner`/`r3d::runner`).
+    /// Belegt weiterhin seinen Scheduler-Slot. Ohne Effekt falls der Job nicht
+    /// `Running` ist.
+    #[serde(rename = "pause_job")]
+    PauseJob { id: String },
+
+    /// Setzt einen per `pause_job` pausierten Job per `SIGCONT` fort. Ohne
+    /// Effekt falls der Job nicht `Paused` ist.
+    #[serde(rename = "resume_job")]
+    ResumeJob { id: String },
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -36,6 +60,17 @@ pub enum Request {
 pub enum JobMode {
     ReWrap,
     Proxy,
+    /// Mehrere Clips mit xfade/acrossfade-Transitions zu einer Timeline verbinden
+    /// (siehe `ffmpeg::concat`). `input_path` ist der erste Clip, weitere ueber
+    /// `JobOptions::concat_clips`/`intro_clip`/`outro_clip`.
+    Concat,
+    /// Fragmented-MP4/HLS-Segment-Stream fuer On-Demand-Delivery (siehe
+    /// `ffmpeg::segmented::run_segmented_job`): statt einer monolithischen Datei
+    /// entstehen eine Playlist (`playlist.m3u8`), ein Init-Segment (nur bei
+    /// `JobOptions::stream_format == "fmp4"`) und fortlaufend nummerierte
+    /// Media-Segmente, die bereits waehrend des laufenden Encodes einzeln
+    /// ausgeliefert werden koennen (siehe `Response::JobSegmentReady`).
+    Stream,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -59,6 +94,269 @@ pub struct JobOptions {
     /// Optionaler Unterordner im Ausgabeverzeichnis, z.B. "proxy". Leer = keiner.
     #[serde(default)]
     pub output_subfolder: String,
+
+    /// Teilt den Input an Keyframe-Grenzen in parallel kodierbare Segmente auf
+    /// und fuegt sie verlustfrei wieder zusammen (nur fuer `JobMode::Proxy`).
+    #[serde(default)]
+    pub chunked_encode: bool,
+
+    /// Nur bei `chunked_encode`: Segmentgrenzen per Szenenerkennung statt
+    /// gleichmaessiger Keyframe-Aufteilung bestimmen (siehe
+    /// `ffmpeg::chunked::probe_scene_chunks`). `false` (Default) = bisheriges
+    /// Verhalten (gleich lange Segmente, Anzahl = CPU-Kerne).
+    #[serde(default)]
+    pub scene_detect: bool,
+
+    /// Schwellwert fuer FFmpegs `select='gt(scene,THRESH)'`-Szenenerkennung
+    /// (0.0–1.0, hoeher = weniger empfindlich). Nur bei `scene_detect`.
+    #[serde(default = "default_scene_detect_threshold")]
+    pub scene_detect_threshold: f32,
+
+    /// Maximale Segmentlaenge in Sekunden bei `scene_detect`: laengere (z.B.
+    /// statische) Szenen werden an weiteren Keyframes nachunterteilt, damit auch
+    /// sie von der Parallelitaet profitieren. `0` = keine Obergrenze.
+    #[serde(default = "default_max_scene_secs")]
+    pub max_scene_secs: f64,
+
+    /// Watchdog-Timeout in Sekunden: wird bei jedem Fortschritts-Tick (Progress-Block
+    /// bzw. NDJSON-Zeile mit steigender `frame`-Zahl) zurueckgesetzt. Laeuft er ab,
+    /// gilt der Prozess als haengengeblieben und wird abgebrochen. `0` = deaktiviert.
+    #[serde(default)]
+    pub process_timeout_secs: u64,
+
+    /// Nur fuer den normalen FFmpeg-Pfad (kein BRAW/R3D): `-progress` ueber einen
+    /// lokalen TcpListener statt interleaved auf stderr. Haelt stderr frei fuer
+    /// reine Diagnosemeldungen. `false` = weiterhin `-progress pipe:2` (Default,
+    /// fuer Plattformen/Builds die den Pipe-Transport bevorzugen).
+    #[serde(default)]
+    pub progress_via_tcp: bool,
+
+    /// Nur fuer `JobMode::Concat`: weitere Clip-Pfade die nach `input_path` an die
+    /// Timeline angehaengt werden (in Reihenfolge).
+    #[serde(default)]
+    pub concat_clips: Vec<String>,
+
+    /// Nur fuer `JobMode::Concat`: optionaler Clip, der der Timeline vorangestellt wird.
+    #[serde(default)]
+    pub intro_clip: Option<String>,
+
+    /// Nur fuer `JobMode::Concat`: optionaler Clip, der an die Timeline angehaengt wird.
+    #[serde(default)]
+    pub outro_clip: Option<String>,
+
+    // Werte: "fade" (xfade "fadeblack") | "dissolve" (xfade "dissolve")
+    #[serde(default = "default_transition_kind")]
+    pub transition_kind: String,
+
+    /// Dauer der Transition in Sekunden (xfade `duration=` / acrossfade `d=`).
+    #[serde(default = "default_transition_duration_secs")]
+    pub transition_duration_secs: f32,
+
+    /// Maximale Anzahl automatischer Wiederholungen bei nicht-deterministischen
+    /// Laufzeitfehlern (Prozess-Spawn/-Exit, z.B. FFmpeg mit Exit-Code != 0).
+    /// `0` (Default) = keine automatischen Retries, Job geht sofort auf `Error`.
+    #[serde(default)]
+    pub max_retries: u32,
+
+    /// Basis-Verzoegerung in Millisekunden fuer den exponentiellen Backoff
+    /// (`retry_base_delay_ms * 2^(attempt-1)`, gedeckelt). Nur relevant wenn
+    /// `max_retries > 0`.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+
+    /// Dispatch-Prioritaet fuer den Ready-Queue-Scheduler in `transcode::run_queue`:
+    /// hoeherer Wert = dringender, wird vor niedriger priorisierten, aber frueher
+    /// angekommenen Jobs gestartet. `0` (Default) = normale Prioritaet.
+    #[serde(default)]
+    pub priority: u8,
+
+    /// Optionaler Upload-Schritt nach erfolgreichem Transcode (siehe
+    /// `jobs::upload::upload_output`): `job.output_path()` wird statt (bzw.
+    /// zusaetzlich zu) des lokalen `output_dir` zu einem S3-kompatiblen Ziel
+    /// hochgeladen. `None` (Default) = kein Upload, Job gilt wie bisher nach
+    /// dem Transcode als fertig.
+    #[serde(default)]
+    pub upload_destination: Option<UploadDestination>,
+
+    /// Ziel-VMAF-Score fuer automatische CRF/QP-Konvergenz per Probing (siehe
+    /// `ffmpeg::vmaf::converge_crf`). Gilt fuer `proxy_codec` h264/h265/av1, sowohl
+    /// Software (CRF) als auch VAAPI/NVENC (QP, siehe `hw_accel` und
+    /// `RateControl::ConstQp`); ProRes (festes Profil) ignoriert diese Option.
+    /// `None` (Default) = feste CRF/QP.
+    #[serde(default)]
+    pub target_vmaf: Option<f32>,
+
+    /// Untere CRF/QP-Grenze fuer die Probe-Schleife bei `target_vmaf` (QP bei
+    /// `hw_accel` == `"vaapi"`/`"nvenc"`, siehe dortigen Kommentar).
+    #[serde(default = "default_vmaf_crf_min")]
+    pub vmaf_crf_min: u32,
+
+    /// Obere CRF/QP-Grenze fuer die Probe-Schleife bei `target_vmaf` (QP bei
+    /// `hw_accel` == `"vaapi"`/`"nvenc"`, siehe dortigen Kommentar).
+    #[serde(default = "default_vmaf_crf_max")]
+    pub vmaf_crf_max: u32,
+
+    /// Toleranz in VMAF-Punkten: eine Probe gilt als Konvergenzpunkt, sobald sie
+    /// hoechstens um diesen Wert von `target_vmaf` abweicht.
+    #[serde(default = "default_vmaf_tolerance")]
+    pub vmaf_tolerance: f32,
+
+    /// Maximale Anzahl Probe-Encodes bei `target_vmaf`, bevor mit dem bisher
+    /// besten Ergebnis abgebrochen wird.
+    #[serde(default = "default_vmaf_max_probes")]
+    pub vmaf_max_probes: u32,
+
+    /// Manuell gesetzte Transfer-Charakteristik fuer den Output (z.B. "smpte2084"
+    /// fuer PQ, "arib-std-b67" fuer HLG, "bt709" fuer SDR). Container-Tags der
+    /// Quelle sind in der Praxis oft falsch gesetzt, daher hat dieser Wert Vorrang
+    /// vor dem geprobten Wert aus `probe::probe_media_metadata`. `None` (Default) =
+    /// geprobten Wert der Quelle uebernehmen.
+    #[serde(default)]
+    pub color_transfer: Option<String>,
+
+    /// Manuell gesetzte Farbraum-Primaries fuer den Output (z.B. "bt2020").
+    /// Siehe `color_transfer` fuer das Vorrang-/Fallback-Verhalten.
+    #[serde(default)]
+    pub color_primaries: Option<String>,
+
+    /// Manuell gesetzte Matrix-Koeffizienten fuer den Output (z.B. "bt2020nc").
+    /// Siehe `color_transfer` fuer das Vorrang-/Fallback-Verhalten.
+    #[serde(default)]
+    pub color_space: Option<String>,
+
+    /// Opt-in Tone-Mapping auf SDR fuer Nutzer die explizit einen SDR-Output
+    /// wollen (`zscale`/`tonemap` im Hybrid-Pfad, `tonemap_cuda` im
+    /// Full-GPU-Pfad; siehe `ffmpeg::runner::push_proxy_codec_args`). Greift nur
+    /// wenn die Quelle tatsaechlich PQ/HLG ist. `false` (Default) = HDR-Metadaten
+    /// unveraendert durchreichen, PQ/HLG-Quellen bleiben PQ/HLG.
+    #[serde(default)]
+    pub tonemap: bool,
+
+    /// Segment-Container-Typ fuer `JobMode::Stream`: "fmp4" = fragmentiertes MP4
+    /// (CMAF-Segmente `segment_%05d.m4s` + eigenes Init-Segment `init.mp4`),
+    /// "hls" = klassische MPEG-TS-Segmente (`segment_%05d.ts`, kein Init-Segment).
+    /// Ohne Effekt ausserhalb von `JobMode::Stream`.
+    #[serde(default = "default_stream_format")]
+    pub stream_format: String,
+
+    /// Segmentlaenge in Sekunden (HLS `#EXTINF`/`-hls_time`): bestimmt die
+    /// Seek-Granularitaet der Playlist. Nur fuer `JobMode::Stream`.
+    #[serde(default = "default_fragment_duration_secs")]
+    pub fragment_duration_secs: f64,
+
+    /// Sub-Segment-Fragmentlaenge in Sekunden innerhalb jedes Segments
+    /// (`-frag_duration`, nur bei `stream_format == "fmp4"`): kleinere Werte
+    /// liefern frueher erste Bytes pro Segment fuer latenzarme Auslieferung.
+    /// Ohne Effekt bei `stream_format == "hls"` (MPEG-TS kennt kein
+    /// Sub-Fragmentieren innerhalb eines Segments).
+    #[serde(default = "default_chunk_duration_secs")]
+    pub chunk_duration_secs: f64,
+
+    /// Transport fuer RTSP-Live-Quellen (siehe `ffmpeg::probe::classify_live_source`):
+    /// "tcp" (Default, zuverlaessiger hinter NAT/Firewalls) oder "udp" (geringere
+    /// Latenz, aber paketverlustanfaellig). Ohne Effekt fuer Datei-Inputs.
+    #[serde(default = "default_rtsp_transport")]
+    pub rtsp_transport: String,
+
+    /// Bei RTSP-Verbindungsabbruch automatisch neu verbinden statt den Job sofort
+    /// als fehlgeschlagen zu beenden (z.B. Kamera-Reboot, kurzer Netzwerk-Hickup).
+    /// `true` (Default). Ohne Effekt fuer Datei-Inputs.
+    #[serde(default = "default_rtsp_reconnect")]
+    pub rtsp_reconnect: bool,
+
+    /// Pixel-Format fuer V4L2-Capture-Devices (`-input_format`, z.B. "mjpeg" oder
+    /// "yuyv422"). Wird zugleich als Quelle fuer die NVENC-Full-GPU-Erkennung
+    /// verwendet (siehe `ffmpeg::hwcaps::HwCapabilities::supports_full_gpu`), da
+    /// ein laufendes Capture-Device nicht zusaetzlich per ffprobe abgefragt werden
+    /// kann. `None` (Default) = Geraete-Default, keine Full-GPU-Erkennung.
+    #[serde(default)]
+    pub capture_pix_fmt: Option<String>,
+
+    /// Aufloesung fuer V4L2-Capture-Devices (`-video_size`, Format "1920x1080").
+    /// `None` (Default) = Geraete-Default.
+    #[serde(default)]
+    pub capture_resolution: Option<String>,
+
+    /// Wall-Clock-Limit in Sekunden (`-t`) fuer Live-Quellen ohne bekannte
+    /// Gesamtdauer (siehe `ffmpeg::probe::is_live_source`): der Job laeuft sonst
+    /// bis `Cancel`. `None` (Default) = kein Limit. Ohne Effekt fuer Datei-Inputs
+    /// mit bekannter Dauer.
+    #[serde(default)]
+    pub max_duration_secs: Option<f64>,
+
+    /// Byte-Limit (`-fs`) fuer die Output-Datei, alternativ oder zusaetzlich zu
+    /// `max_duration_secs`. `None` (Default) = kein Limit.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+
+    /// Ratenkontrolle fuer den Proxy-Encode (siehe `ffmpeg::runner::push_proxy_codec_args`).
+    /// `None` (Default) = feste QP/CRF wie bisher (23 fuer H.264/H.265 und VAAPI-AV1,
+    /// 30 fuer Software-AV1, 63 fuer AV1-NVENC).
+    /// Hat Vorrang vor `target_vmaf`s konvergierter CRF nur wenn `target_vmaf`
+    /// `None` ist – beide gleichzeitig zu setzen ergibt keinen Sinn (Ziel-VMAF
+    /// konvergiert ohnehin auf einen QP/CRF-Wert) und `target_vmaf` gewinnt.
+    /// Ohne Effekt fuer ProRes (festes Profil statt Ratenkontrolle).
+    #[serde(default)]
+    pub rate_control: Option<RateControl>,
+
+    /// Streamt den kodierten Proxy direkt zu einem Netzwerk-Ziel statt in
+    /// `output_dir` auf die lokale Platte zu schreiben (siehe
+    /// `ffmpeg::network_sink`). Akzeptiert `http://` (chunked `PUT`, kein TLS),
+    /// `s3://bucket/key` (echter Multipart-Upload, ein Part pro gelesenem Stueck)
+    /// oder `mem://` (In-Memory-Puffer, primaer fuer Tests ohne Netzwerkabhaengigkeit).
+    /// `None` (Default) = wie bisher lokale Datei in `output_dir`. Nur fuer
+    /// `JobMode::Proxy` nicht-ReWrap-Faelle sinnvoll; ignoriert bei
+    /// `Concat`/`Stream`/`ReWrap` sowie bei `chunked_encode`. Noch KEINE echte `avio_alloc_context`-Einbindung
+    /// (siehe `ffmpeg::network_sink`s Modul-Kommentar zum Scope dieser Abweichung).
+    #[serde(default)]
+    pub output_url: Option<String>,
+}
+
+/// Ratenkontrollmodus fuer Software- und Hardware-Encoder (siehe `JobOptions::rate_control`).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RateControl {
+    /// Konstantes QP/CRF wie bisher, aber mit explizitem Wert statt Default 23/30.
+    ConstQp { qp: u32 },
+    /// Variable Bitrate mit Zielwert und Deckel (`-maxrate`), Qualitaet schwankt
+    /// mit der Szenenkomplexitaet. Geeignet fuer Review-Proxies ohne harte
+    /// Groessenvorgabe.
+    Vbr { target_kbps: u32, max_kbps: u32 },
+    /// Konstante Bitrate (Ziel == Deckel): fuer Streaming-Szenarien mit fixer
+    /// Bandbreite statt variabler Qualitaet.
+    Cbr { kbps: u32 },
+    /// Zwei-Pass-Encode: Pass 1 analysiert die Quelle (Output verworfen), Pass 2
+    /// kodiert mit den Statistikdaten aus Pass 1 final auf `target_kbps`. Nur
+    /// fuer CRF-basierte Software-Encoder als echter Zwei-Pass-Lauf umgesetzt
+    /// (siehe `ffmpeg::runner::run_two_pass_ffmpeg`); bei VAAPI/NVENC degradiert
+    /// dies auf einen einzelnen VBR-Lauf mit `target_kbps`, da diese Encoder kein
+    /// `-pass 1/2` unterstuetzen.
+    TwoPass { target_kbps: u32 },
+}
+
+/// Ziel fuer den optionalen Post-Transcode-Upload (siehe `JobOptions::upload_destination`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UploadDestination {
+    /// S3-kompatibler Endpunkt, z.B. "https://s3.eu-central-1.amazonaws.com"
+    /// oder ein selbstgehosteter MinIO-Endpunkt.
+    pub endpoint: String,
+
+    pub bucket: String,
+
+    /// Vorangestellt an den Objekt-Key, z.B. "proxies/2026-07". Leer = keiner.
+    #[serde(default)]
+    pub key_prefix: String,
+
+    #[serde(default = "default_upload_region")]
+    pub region: String,
+
+    pub access_key_id: String,
+
+    pub secret_access_key: String,
+}
+
+fn default_upload_region() -> String {
+    "us-east-1".to_string()
 }
 
 impl Default for JobOptions {
@@ -70,6 +368,41 @@ impl Default for JobOptions {
             hw_accel: default_hw_accel(),
             output_suffix: default_output_suffix(),
             output_subfolder: String::new(),
+            chunked_encode: false,
+            scene_detect: false,
+            scene_detect_threshold: default_scene_detect_threshold(),
+            max_scene_secs: default_max_scene_secs(),
+            process_timeout_secs: 0,
+            progress_via_tcp: false,
+            concat_clips: Vec::new(),
+            intro_clip: None,
+            outro_clip: None,
+            transition_kind: default_transition_kind(),
+            transition_duration_secs: default_transition_duration_secs(),
+            max_retries: 0,
+            retry_base_delay_ms: default_retry_base_delay_ms(),
+            priority: 0,
+            upload_destination: None,
+            target_vmaf: None,
+            vmaf_crf_min: default_vmaf_crf_min(),
+            vmaf_crf_max: default_vmaf_crf_max(),
+            vmaf_tolerance: default_vmaf_tolerance(),
+            vmaf_max_probes: default_vmaf_max_probes(),
+            color_transfer: None,
+            color_primaries: None,
+            color_space: None,
+            tonemap: false,
+            stream_format: default_stream_format(),
+            fragment_duration_secs: default_fragment_duration_secs(),
+            chunk_duration_secs: default_chunk_duration_secs(),
+            rtsp_transport: default_rtsp_transport(),
+            rtsp_reconnect: default_rtsp_reconnect(),
+            capture_pix_fmt: None,
+            capture_resolution: None,
+            max_duration_secs: None,
+            max_bytes: None,
+            rate_control: None,
+            output_url: None,
         }
     }
 }
@@ -90,6 +423,62 @@ fn default_output_suffix() -> String {
     String::new()
 }
 
+fn default_transition_kind() -> String {
+    "fade".to_string()
+}
+
+fn default_transition_duration_secs() -> f32 {
+    1.0
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    1000
+}
+
+fn default_scene_detect_threshold() -> f32 {
+    0.3
+}
+
+fn default_max_scene_secs() -> f64 {
+    30.0
+}
+
+fn default_vmaf_crf_min() -> u32 {
+    18
+}
+
+fn default_vmaf_crf_max() -> u32 {
+    35
+}
+
+fn default_vmaf_tolerance() -> f32 {
+    0.5
+}
+
+fn default_vmaf_max_probes() -> u32 {
+    4
+}
+
+fn default_stream_format() -> String {
+    "fmp4".to_string()
+}
+
+fn default_fragment_duration_secs() -> f64 {
+    6.0
+}
+
+fn default_chunk_duration_secs() -> f64 {
+    1.0
+}
+
+fn default_rtsp_transport() -> String {
+    "tcp".to_string()
+}
+
+fn default_rtsp_reconnect() -> bool {
+    true
+}
+
 // ---------------------------------------------------------------------------
 // Ausgehend (zu Python)
 // ---------------------------------------------------------------------------
@@ -107,6 +496,12 @@ pub enum Response {
         fps: f32,
         speed: f32,
         frame: u64,
+        /// Verstrichene Encode-Zeit in Mikrosekunden (`out_time_us` aus dem
+        /// FFmpeg-Progress-Block). Fuer Live-Quellen ohne bekannte Gesamtdauer
+        /// (siehe `ffmpeg::probe::is_live_source`) bleibt `percent` bei 0 –
+        /// hier ist `elapsed_us` (zusammen mit `frame`/`fps`) das massgebliche
+        /// Fortschrittssignal.
+        elapsed_us: i64,
     },
 
     #[serde(rename = "job_done")]
@@ -118,8 +513,97 @@ pub enum Response {
     #[serde(rename = "job_cancelled")]
     JobCancelled { id: String },
 
+    /// Ein Job ist fehlgeschlagen, wird aber erneut eingereiht (siehe
+    /// `JobOptions::max_retries`) statt als `JobError` abgeschlossen zu werden.
+    #[serde(rename = "job_retrying")]
+    JobRetrying { id: String, attempt: u32, delay_ms: u64 },
+
+    /// Fortschritt eines laufenden Drains (siehe `Request::Drain`). `remaining == 0`
+    /// bedeutet: Drain abgeschlossen, alle Jobs sind beendet.
+    #[serde(rename = "drain_status")]
+    DrainStatus { remaining: usize },
+
+    /// Post-Transcode-Upload laeuft (siehe `JobOptions::upload_destination`).
+    #[serde(rename = "job_uploading")]
+    JobUploading { id: String, percent: f32 },
+
+    /// Upload abgeschlossen; `url` verweist auf das hochgeladene Objekt.
+    #[serde(rename = "job_uploaded")]
+    JobUploaded { id: String, url: String },
+
+    /// Ein neues Media-Segment eines laufenden `JobMode::Stream`-Jobs wurde fertig
+    /// geschrieben (siehe `ffmpeg::segmented::run_segmented_job`). `segment_path`
+    /// kann sofort an einen Client ausgeliefert werden, waehrend spaetere
+    /// Segmente noch kodiert werden; `index` ist 0-basiert und fortlaufend.
+    #[serde(rename = "job_segment_ready")]
+    JobSegmentReady { id: String, segment_path: String, index: u32 },
+
+    /// Ein Job ist endgueltig gescheitert, nachdem der Retry-Broker (siehe
+    /// `ffmpeg::retry::classify_failure`) die Fehlerursache klassifiziert hat.
+    /// Unterscheidet fuer den IPC-Client, ob der Fehler von Anfang an permanent
+    /// war (`fatal`, kein Retry versucht) oder ob `attempts` Retries ausgeschoepft
+    /// wurden (`retries_exhausted`) – im Gegensatz zu `JobError`, das keine
+    /// Klassifikation traegt und weiterhin fuer nicht-FFmpeg-Fehlschlaege
+    /// (Upload, Panik) genutzt wird.
+    #[serde(rename = "job_failed_final")]
+    JobFailedFinal {
+        id: String,
+        attempts: u32,
+        classification: FailureOutcome,
+        message: String,
+    },
+
+    /// Die angeforderte `proxy_codec`/`hw_accel`-Kombination ist auf diesem Host
+    /// nicht verfuegbar (siehe `ffmpeg::hwcaps::HwCapabilities::resolve_hw_accel`);
+    /// der Job laeuft stattdessen mit Software-Encoding. Nicht terminal – es folgen
+    /// die ueblichen `JobProgress`/`JobDone`/`JobError`-Events fuer denselben Job.
+    #[serde(rename = "job_hw_fallback")]
+    JobHwFallback { id: String, message: String },
+
+    /// `JobOptions::target_vmaf` wurde gesetzt, aber zusammen mit `chunked_encode`
+    /// ignoriert (siehe `jobs::transcode::run_queue`s `wants_target_vmaf`) – der
+    /// Chunked-Pfad (`ffmpeg::chunked::build_chunk_args`) kennt keinen `crf_override`.
+    /// Nicht terminal – es folgen die ueblichen `JobProgress`/`JobDone`/`JobError`-
+    /// Events fuer denselben Job.
+    #[serde(rename = "job_target_vmaf_ignored")]
+    JobTargetVmafIgnored { id: String, message: String },
+
     #[serde(rename = "status_report")]
     StatusReport { jobs: Vec<JobStatus> },
+
+    /// Ein Job wurde per `Request::PauseJob` pausiert (siehe `JobState::Paused`).
+    #[serde(rename = "job_paused")]
+    JobPaused { id: String },
+
+    /// Ein zuvor pausierter Job laeuft per `Request::ResumeJob` weiter.
+    #[serde(rename = "job_resumed")]
+    JobResumed { id: String },
+}
+
+/// Endgueltige Klassifikation eines `Response::JobFailedFinal` (siehe
+/// `ffmpeg::retry::FailureClass` fuer die interne, feingranularere Variante die
+/// auch ueber HW-Degradierung entscheidet).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureOutcome {
+    /// Fehler war von Anfang an nicht durch Retry behebbar, es wurde kein
+    /// Versuch unternommen.
+    Fatal,
+    /// Fehler galt als transient, aber `JobOptions::max_retries` wurde
+    /// ausgeschoepft ohne Erfolg.
+    RetriesExhausted,
+}
+
+/// Concurrency-Lane, die ein Job im Scheduler belegt (siehe
+/// `jobs::transcode::job_lane`): GPU-Encoder sind typischerweise einzeln im
+/// System vorhanden und duerfen nicht durch viele CPU-Proxies oversubscribed
+/// werden, daher getrennte Kapazitaeten (`--max-parallel-cpu`/
+/// `--max-parallel-gpu`) und getrennte Ready-Queues pro Lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    Cpu,
+    Gpu,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -129,13 +613,23 @@ pub struct JobStatus {
     pub mode: JobMode,
     pub status: JobState,
     pub percent: f32,
+    /// Position in der lane-eigenen Ready-Queue (1-basiert), nur gesetzt wenn
+    /// `status == Queued` (siehe `Lane`).
+    pub queue_position: Option<usize>,
+    /// Welche Lane diesen Job dispatcht hat, nur gesetzt wenn `status` `Running`
+    /// oder `Paused` ist.
+    pub lane: Option<Lane>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum JobState {
     Queued,
     Running,
+    /// Per `SIGSTOP` angehalten (siehe `Request::PauseJob`). Belegt weiterhin
+    /// seinen Scheduler-Slot; Fortschritt friert ein, da der Prozess keine
+    /// weiteren Progress-Ticks mehr emittiert, solange er gestoppt ist.
+    Paused,
     Done,
     Error,
     Cancelled,
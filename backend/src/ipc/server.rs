@@ -1,21 +1,122 @@
-// IPC-Server: Liest JSON-Requests von stdin, schreibt Responses auf stdout (NDJSON).
+// IPC-Server: Liest JSON-Requests von stdin oder ueber TCP (`--listen`), schreibt
+// Responses als NDJSON zurueck. `ipc::protocol` bleibt fuer beide Transporte die
+// einzige Quelle der Wahrheit fuer das Request/Response-Format.
 
 use std::sync::Arc;
 
 use anyhow::Result;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::sync::mpsc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
 
 use crate::ipc::protocol::{Request, Response};
 use crate::jobs::transcode::{Job, JobQueue};
 
-/// Liest Requests von stdin und gibt sie via Sender weiter.
+/// Verarbeitet einen einzelnen geparsten `Request` gegen die `JobQueue`.
+/// Unmittelbare Fehler (z.B. unbekannte Job-ID) gehen ueber `response_tx` genau
+/// wie die regulaeren Queue-Events – beide landen am Ende im selben
+/// Broadcast-Hub (siehe `main.rs`) und erreichen damit alle verbundenen
+/// Sessions, nicht nur die, die den Request gestellt hat. Von `read_stdin` und
+/// den TCP-Sessions (siehe `handle_tcp_session`) gemeinsam genutzt, damit beide
+/// Transporte exakt denselben Dispatch durchlaufen.
+///
+/// Gibt `true` zurueck, wenn der aufrufende Transport danach beendet werden
+/// soll (nur bei `Request::Shutdown`, das global ueber `shutdown_token`
+/// signalisiert wird).
+async fn handle_request(
+    request: Request,
+    queue: &Arc<JobQueue>,
+    response_tx: &mpsc::Sender<Response>,
+    shutdown_token: &CancellationToken,
+) -> bool {
+    match request {
+        Request::AddJob { id, input_path, output_dir, mode, options } => {
+            let job_id = id.clone();
+            let job = Job::new(id, input_path, output_dir, mode, options);
+            if let Err(e) = queue.add_job(job).await {
+                eprintln!("Fehler beim Hinzufuegen des Jobs: {e}");
+                let _ = response_tx
+                    .send(Response::JobError { id: job_id, message: format!("Job konnte nicht hinzugefuegt werden: {e}") })
+                    .await;
+            }
+            false
+        }
+        Request::CancelJob { id } => {
+            let cancel_id = id.clone();
+            if let Err(e) = queue.cancel_job(id).await {
+                eprintln!("Fehler beim Abbrechen des Jobs: {e}");
+                let _ = response_tx
+                    .send(Response::JobError { id: cancel_id, message: format!("Job konnte nicht abgebrochen werden: {e}") })
+                    .await;
+            }
+            false
+        }
+        Request::GetStatus => {
+            match queue.get_status().await {
+                Ok(statuses) => {
+                    let response = Response::StatusReport { jobs: statuses };
+                    if let Err(e) = response_tx.send(response).await {
+                        eprintln!("Fehler beim Senden der Status-Response: {e}");
+                    }
+                }
+                Err(e) => eprintln!("Fehler beim Abfragen des Status: {e}"),
+            }
+            false
+        }
+        Request::Shutdown => {
+            // Global statt lokal: egal ueber welchen Transport der Shutdown
+            // ankommt, er muss den ganzen Prozess beenden (siehe `main.rs`s
+            // Top-Level-`select!` auf `global_shutdown_token`).
+            shutdown_token.cancel();
+            true
+        }
+        Request::Drain => {
+            if let Err(e) = queue.drain().await {
+                eprintln!("Fehler beim Starten des Drain: {e}");
+            }
+            false
+        }
+        Request::SetPriority { id, priority } => {
+            let prio_id = id.clone();
+            if let Err(e) = queue.set_priority(id, priority).await {
+                eprintln!("Fehler beim Setzen der Prioritaet: {e}");
+                let _ = response_tx
+                    .send(Response::JobError { id: prio_id, message: format!("Prioritaet konnte nicht gesetzt werden: {e}") })
+                    .await;
+            }
+            false
+        }
+        Request::PauseJob { id } => {
+            let pause_id = id.clone();
+            if let Err(e) = queue.pause_job(id).await {
+                eprintln!("Fehler beim Pausieren des Jobs: {e}");
+                let _ = response_tx
+                    .send(Response::JobError { id: pause_id, message: format!("Job konnte nicht pausiert werden: {e}") })
+                    .await;
+            }
+            false
+        }
+        Request::ResumeJob { id } => {
+            let resume_id = id.clone();
+            if let Err(e) = queue.resume_job(id).await {
+                eprintln!("Fehler beim Fortsetzen des Jobs: {e}");
+                let _ = response_tx
+                    .send(Response::JobError { id: resume_id, message: format!("Job konnte nicht fortgesetzt werden: {e}") })
+                    .await;
+            }
+            false
+        }
+    }
+}
+
+/// Liest Requests von stdin und gibt sie via `handle_request` weiter.
 /// Alle Responses werden ueber `response_tx` gesendet, damit nur ein
 /// einziger Writer-Task auf stdout schreibt (keine Race Condition).
 pub async fn read_stdin(
     queue: Arc<JobQueue>,
     response_tx: mpsc::Sender<Response>,
-    shutdown_tx: tokio::sync::oneshot::Sender<()>,
+    shutdown_token: CancellationToken,
 ) -> Result<()> {
     let stdin = tokio::io::stdin();
     let mut reader = BufReader::new(stdin).lines();
@@ -34,49 +135,8 @@ pub async fn read_stdin(
             }
         };
 
-        match request {
-            Request::AddJob {
-                id,
-                input_path,
-                output_dir,
-                mode,
-                options,
-            } => {
-                let job_id = id.clone();
-                let job = Job::new(id, input_path, output_dir, mode, options);
-                if let Err(e) = queue.add_job(job).await {
-                    eprintln!("Fehler beim Hinzufuegen des Jobs: {e}");
-                    let _ = response_tx.send(Response::JobError {
-                        id: job_id,
-                        message: format!("Job konnte nicht hinzugefuegt werden: {e}"),
-                    }).await;
-                }
-            }
-            Request::CancelJob { id } => {
-                let cancel_id = id.clone();
-                if let Err(e) = queue.cancel_job(id).await {
-                    eprintln!("Fehler beim Abbrechen des Jobs: {e}");
-                    let _ = response_tx.send(Response::JobError {
-                        id: cancel_id,
-                        message: format!("Job konnte nicht abgebrochen werden: {e}"),
-                    }).await;
-                }
-            }
-            Request::GetStatus => {
-                match queue.get_status().await {
-                    Ok(statuses) => {
-                        let response = Response::StatusReport { jobs: statuses };
-                        if let Err(e) = response_tx.send(response).await {
-                            eprintln!("Fehler beim Senden der Status-Response: {e}");
-                        }
-                    }
-                    Err(e) => eprintln!("Fehler beim Abfragen des Status: {e}"),
-                }
-            }
-            Request::Shutdown => {
-                let _ = shutdown_tx.send(());
-                return Ok(());
-            }
+        if handle_request(request, &queue, &response_tx, &shutdown_token).await {
+            return Ok(());
         }
     }
 
@@ -84,12 +144,26 @@ pub async fn read_stdin(
 }
 
 /// Schreibt Response-Events als NDJSON auf stdout.
-/// Laeuft als eigener Task. Gibt Fehler zurueck wenn die stdout-Pipe geschlossen wird.
-pub async fn write_stdout(mut rx: mpsc::Receiver<Response>) -> Result<()> {
+/// Laeuft als eigener Task und abonniert dazu den Broadcast-Hub aus `main.rs`
+/// (denselben, an dem auch TCP-Clients haengen) statt eines eigenen Channels,
+/// damit stdout immer dieselben Events sieht wie jeder andere Transport.
+/// Faellt eine Nachricht durch einen vollen Ringpuffer weg (`Lagged`), wird das
+/// nur geloggt statt den Task zu beenden – stdout haengt sonst dauerhaft hinter
+/// der Queue zurueck.
+pub async fn write_stdout(mut rx: broadcast::Receiver<Response>) -> Result<()> {
     let stdout = tokio::io::stdout();
     let mut writer = BufWriter::new(stdout);
 
-    while let Some(response) = rx.recv().await {
+    loop {
+        let response = match rx.recv().await {
+            Ok(response) => response,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                eprintln!("stdout-Writer: {n} Responses durch vollen Broadcast-Puffer verpasst");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
         let json = match serde_json::to_string(&response) {
             Ok(j) => j,
             Err(e) => {
@@ -97,7 +171,6 @@ pub async fn write_stdout(mut rx: mpsc::Receiver<Response>) -> Result<()> {
                 continue;
             }
         };
-
         writer.write_all(json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -105,3 +178,104 @@ pub async fn write_stdout(mut rx: mpsc::Receiver<Response>) -> Result<()> {
 
     Ok(())
 }
+
+/// TCP-Gegenstueck zu stdin/stdout (`--listen <addr>`): serviert dasselbe
+/// NDJSON-Protokoll fuer entfernte Dashboards/UIs, die nicht als Kindprozess
+/// mit Pipes gestartet werden. Nimmt Verbindungen entgegen, bis entweder
+/// `listener.accept()` fehlschlaegt oder der globale Shutdown eintritt; jede
+/// Verbindung bekommt eine eigene Session (siehe `handle_tcp_session`).
+pub async fn run_tcp_listener(
+    addr: String,
+    queue: Arc<JobQueue>,
+    response_tx: mpsc::Sender<Response>,
+    response_broadcast: broadcast::Sender<Response>,
+    shutdown_token: CancellationToken,
+) -> Result<()> {
+    let listener = TcpListener::bind(&addr).await?;
+    eprintln!("IPC-TCP-Server lauscht auf {addr}");
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            () = shutdown_token.cancelled() => break,
+        };
+        eprintln!("TCP-Client verbunden: {peer}");
+
+        let session_queue = queue.clone();
+        let session_response_tx = response_tx.clone();
+        let session_response_rx = response_broadcast.subscribe();
+        let session_shutdown_token = shutdown_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_tcp_session(
+                stream,
+                session_queue,
+                session_response_tx,
+                session_response_rx,
+                session_shutdown_token,
+            )
+            .await
+            {
+                eprintln!("TCP-Client {peer} getrennt: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Eine einzelne TCP-Verbindung: liest NDJSON-Requests zeilenweise (dispatcht
+/// via `handle_request`) und schreibt gleichzeitig jede Response vom
+/// Broadcast-Hub zurueck, bis die Verbindung schliesst, der Client einen
+/// `Shutdown`-Request schickt, oder der globale Shutdown eintritt.
+async fn handle_tcp_session(
+    stream: TcpStream,
+    queue: Arc<JobQueue>,
+    response_tx: mpsc::Sender<Response>,
+    mut response_rx: broadcast::Receiver<Response>,
+    shutdown_token: CancellationToken,
+) -> Result<()> {
+    let (read_half, write_half) = stream.into_split();
+    let mut reader = BufReader::new(read_half).lines();
+    let mut writer = BufWriter::new(write_half);
+
+    loop {
+        tokio::select! {
+            line = reader.next_line() => {
+                let Some(line) = line? else { break; };
+                let line = line.trim().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let request: Request = match serde_json::from_str(&line) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("Ungueltige JSON-Nachricht von TCP-Client: {e}");
+                        continue;
+                    }
+                };
+
+                if handle_request(request, &queue, &response_tx, &shutdown_token).await {
+                    break;
+                }
+            }
+            response = response_rx.recv() => {
+                let response = match response {
+                    Ok(response) => response,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        eprintln!("TCP-Client: {n} Responses durch vollen Broadcast-Puffer verpasst");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let json = serde_json::to_string(&response)?;
+                writer.write_all(json.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+            () = shutdown_token.cancelled() => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,6 @@
+// Job-Subsystem: Queue/Scheduling (transcode), persistente Ablage (store)
+// und optionaler Post-Transcode-Upload (upload).
+
+pub mod store;
+pub mod transcode;
+pub mod upload;
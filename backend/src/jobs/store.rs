@@ -0,0 +1,110 @@
+// Persistente Job-Ablage: haelt jeden Queued/Running-Job in einer embedded
+// sled-Datenbank, damit ein Absturz oder Neustart des Backends nicht stillschweigend
+// alle wartenden/laufenden Transcodes verliert. Wird von `transcode::run_queue`
+// bei jedem `JobCommand::Add` geschrieben, bei jedem `FfmpegEvent` aktualisiert und
+// bei Job-Abschluss geloescht. Beim Start liest `JobQueue::new` den Store erneut ein
+// und reiht alle noch offenen Jobs wieder ein (siehe `load_recoverable`).
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::ipc::protocol::{JobMode, JobOptions, JobState};
+use crate::jobs::transcode::Job;
+
+/// Serialisierbares Abbild eines `Job` (ohne `cancel_token` – das ist reine
+/// Laufzeit-Koordination und wird beim Wiedereinlesen neu erzeugt).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedJob {
+    id: String,
+    input_path: std::path::PathBuf,
+    output_dir: std::path::PathBuf,
+    mode: JobMode,
+    options: JobOptions,
+    status: JobState,
+    percent: f32,
+    attempt: u32,
+    priority: u8,
+}
+
+impl From<&Job> for PersistedJob {
+    fn from(job: &Job) -> Self {
+        Self {
+            id: job.id.clone(),
+            input_path: job.input_path.clone(),
+            output_dir: job.output_dir.clone(),
+            mode: job.mode.clone(),
+            options: job.options.clone(),
+            status: job.status.clone(),
+            percent: job.percent,
+            attempt: job.attempt,
+            priority: job.priority,
+        }
+    }
+}
+
+impl PersistedJob {
+    /// Baut einen lauffaehigen `Job` mit frischem `CancellationToken`. Ein beim
+    /// Absturz unterbrochener `Running`-Job wird auf `Queued`/0% zurueckgesetzt –
+    /// die Output-Datei ist unvollstaendig und muss von vorn kodiert werden.
+    fn into_job(self) -> Job {
+        let restart_from_zero = matches!(self.status, JobState::Running);
+        Job {
+            id: self.id,
+            input_path: self.input_path,
+            output_dir: self.output_dir,
+            mode: self.mode,
+            options: self.options,
+            status: if restart_from_zero { JobState::Queued } else { self.status },
+            percent: if restart_from_zero { 0.0 } else { self.percent },
+            attempt: self.attempt,
+            priority: self.priority,
+            cancel_token: CancellationToken::new(),
+        }
+    }
+}
+
+/// Duenner Wrapper um eine sled-Datenbank. Billig klonbar (sled::Db ist intern
+/// bereits Arc-basiert), kann also wie `PidSet` frei zwischen Tasks geteilt werden.
+#[derive(Clone)]
+pub struct JobStore {
+    db: sled::Db,
+}
+
+impl JobStore {
+    /// Oeffnet (oder legt an) die sled-Datenbank am gegebenen Pfad.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let db = sled::open(path.as_ref()).context("Job-Store konnte nicht geoeffnet werden")?;
+        Ok(Self { db })
+    }
+
+    /// Schreibt den aktuellen Zustand eines Jobs durch (Add oder Progress-Update).
+    pub fn put(&self, job: &Job) -> Result<()> {
+        let persisted = PersistedJob::from(job);
+        let bytes = serde_json::to_vec(&persisted).context("Job konnte nicht serialisiert werden")?;
+        self.db.insert(job.id.as_bytes(), bytes)?;
+        Ok(())
+    }
+
+    /// Entfernt einen Job nach terminalem Abschluss (Done/Error/Cancelled).
+    pub fn remove(&self, job_id: &str) -> Result<()> {
+        self.db.remove(job_id.as_bytes())?;
+        Ok(())
+    }
+
+    /// Liest alle gespeicherten Jobs ein und gibt die wiederaufsetzbaren zurueck
+    /// (`Queued` unveraendert, `Running` zurueckgesetzt auf `Queued`/0%). Fehlerhafte
+    /// Eintraege werden uebersprungen statt den gesamten Start zu blockieren.
+    pub fn load_recoverable(&self) -> Vec<Job> {
+        self.db
+            .iter()
+            .values()
+            .filter_map(|res| res.ok())
+            .filter_map(|bytes| serde_json::from_slice::<PersistedJob>(&bytes).ok())
+            .filter(|p| matches!(p.status, JobState::Queued | JobState::Running))
+            .map(PersistedJob::into_job)
+            .collect()
+    }
+}
@@ -1,21 +1,141 @@
 // Job-Queue: Verwaltet Transcode-Jobs mit konfigurierbarer Parallelitaet.
-// Nutzt tokio::sync::mpsc fuer Job-Eingang und Semaphore fuer Parallelitaet.
+// Nutzt tokio::sync::mpsc fuer Job-Eingang; die Parallelitaet selbst ist ein
+// Token-Budget-Scheduler (siehe `run_queue`/`try_dispatch`) mit getrennten
+// Kapazitaeten fuer die CPU- und die GPU-Lane (siehe `job_lane`).
 
-use std::collections::HashMap;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
-use tokio::sync::{mpsc, Notify, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use tokio_util::sync::CancellationToken;
 
 use crate::braw::runner as braw_runner;
 use crate::r3d::runner as r3d_runner;
-use crate::ffmpeg::runner::{self, build_ffmpeg_args, FfmpegEvent};
+use crate::ffmpeg::chunked;
+use crate::ffmpeg::concat;
+use crate::ffmpeg::probe;
+use crate::ffmpeg::hwcaps::HwCapabilities;
+use crate::ffmpeg::network_sink;
+use crate::ffmpeg::retry::{classify_failure, FailureClass};
+use crate::ffmpeg::runner::{self, build_ffmpeg_args, is_prores, FfmpegEvent};
+use crate::ffmpeg::segmented;
+use crate::ffmpeg::vmaf;
 #[allow(unused_imports)]
 use libc;
-use crate::ipc::protocol::{JobMode, JobOptions, JobState, JobStatus, Response};
+use crate::ipc::protocol::{FailureOutcome, JobMode, JobOptions, JobState, JobStatus, Lane, RateControl, Response};
+use crate::jobs::store::JobStore;
+
+/// Saemtliche PIDs, die zu einem einzelnen Job gehoeren. Normalerweise genau
+/// eine (FFmpeg), bei BRAW/R3D-Bridge-Pipelines zwei (Bridge-Prozess UND der
+/// nachgeschaltete FFmpeg, siehe `braw::runner::run_braw_job`/
+/// `r3d::runner::run_r3d_job`), beim Chunked-Encoding eine pro parallel
+/// laufendem Segment-Prozess. `signal_all` (z.B. SIGSTOP/SIGCONT fuer
+/// Pause/Resume) trifft immer alle registrierten PIDs gleichzeitig.
+#[derive(Clone)]
+pub struct PidSet {
+    slots: Arc<RwLock<Vec<Arc<AtomicU32>>>>,
+    /// Pause-Status fuer den Stall-Watchdog in `ffmpeg::runner::run_ffmpeg`
+    /// (siehe `paused_rx`) – unabhaengig vom SIGSTOP/SIGCONT, das `signal_all`
+    /// an die OS-Prozesse schickt, da der Watchdog rein in-process zaehlt und
+    /// von einem SIGSTOP'ten Prozess nichts mitbekommt.
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Default for PidSet {
+    fn default() -> Self {
+        let (paused, _) = watch::channel(false);
+        Self { slots: Arc::new(RwLock::new(Vec::new())), paused: Arc::new(paused) }
+    }
+}
+
+impl PidSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registriert einen neuen PID-Slot (0 = noch nicht gestartet) und gibt ihn zurueck,
+    /// damit der aufrufende Runner die tatsaechliche PID hineinschreiben kann.
+    pub async fn register(&self) -> Arc<AtomicU32> {
+        let slot = Arc::new(AtomicU32::new(0));
+        self.slots.write().await.push(slot.clone());
+        slot
+    }
+
+    /// Sendet das gegebene Signal an alle aktuell registrierten, laufenden PIDs.
+    pub async fn signal_all(&self, sig: libc::c_int) {
+        for slot in self.slots.read().await.iter() {
+            let pid = slot.load(Ordering::Acquire);
+            if pid != 0 {
+                unsafe {
+                    libc::kill(pid as libc::pid_t, sig);
+                }
+            }
+        }
+    }
+
+    /// Markiert den Job als pausiert/fortgesetzt, damit `run_ffmpeg`s Stall-
+    /// Watchdog eine SIGSTOP-Pause nicht faelschlich als haengenden Prozess
+    /// behandelt (siehe `JobCommand::PauseJob`/`PauseAll`/`ResumeJob`/`ResumeAll`).
+    pub fn set_paused(&self, paused: bool) {
+        let _ = self.paused.send(paused);
+    }
+
+    /// Watch-Receiver auf den aktuellen Pause-Status, den `run_ffmpeg`s
+    /// Stall-Watchdog abonniert.
+    pub fn paused_rx(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+}
+
+/// Backoff-Delay fuer den naechsten Retry-Versuch: `base * 2^(attempt-1)`,
+/// gedeckelt bei 60s damit ein hoher `max_retries`-Wert nicht zu absurd langen
+/// Wartezeiten fuehrt.
+fn retry_delay_ms(base_ms: u64, attempt: u32) -> u64 {
+    const CEILING_MS: u64 = 60_000;
+    let shift = attempt.saturating_sub(1).min(32);
+    base_ms.saturating_mul(1u64 << shift).min(CEILING_MS)
+}
+
+/// GPU- oder CPU-Lane fuer einen Job (siehe `JobCommand::SetMaxParallelCpu`/
+/// `SetMaxParallelGpu`): getrennte Kapazitaeten und Ready-Queues verhindern,
+/// dass viele CPU-Proxies den typischerweise einzigen GPU-Encoder blockieren
+/// oder umgekehrt ein einzelner NVENC/VAAPI-Job alle CPU-Slots belegt. BRAW
+/// laeuft immer ueber `hw_accel = "none"` (siehe `braw::runner`), R3D und
+/// normale Proxy/Stream-Jobs haengen von der gewaehlten `hw_accel`-Option ab.
+fn job_lane(mode: &JobMode, options: &JobOptions) -> Lane {
+    let uses_hw_encoder = matches!(options.hw_accel.as_str(), "nvenc" | "vaapi")
+        && !is_prores(&options.proxy_codec);
+    match mode {
+        JobMode::ReWrap | JobMode::Concat | JobMode::BrawProxy => Lane::Cpu,
+        JobMode::R3dProxy | JobMode::Proxy | JobMode::Stream => {
+            if uses_hw_encoder {
+                Lane::Gpu
+            } else {
+                Lane::Cpu
+            }
+        }
+    }
+}
+
+/// Token-Kosten eines Jobs innerhalb seiner Lane (siehe `job_lane`) fuer den
+/// Budget-Scheduler in `run_queue`. Gilt nur fuer die CPU-Lane – GPU-Jobs
+/// kosten dort immer 1 Slot, da die GPU-Lane nicht ueber ein Kostengewicht
+/// sondern schlicht ueber die Anzahl gleichzeitiger Hardware-Encoder-Sessions
+/// begrenzt wird (siehe `try_dispatch`). ReWrap/Concat sind Stream-Copy bzw.
+/// ein einzelner CPU-x264-Lauf (guenstig), BRAW/R3D-Bridges sind am teuersten
+/// (eigener Debayer-Prozess plus CPU-Encode).
+fn job_cost(mode: &JobMode) -> usize {
+    match mode {
+        JobMode::ReWrap => 1,
+        JobMode::Concat => 2,
+        JobMode::BrawProxy | JobMode::R3dProxy => 3,
+        JobMode::Proxy | JobMode::Stream => 2,
+    }
+}
 
 /// Validiert einen Pfad gegen Path-Traversal-Angriffe.
 /// Stellt sicher, dass der kanonische Pfad nicht ausserhalb erlaubter Bereiche liegt.
@@ -34,6 +154,13 @@ pub struct Job {
     pub options: JobOptions,
     pub status: JobState,
     pub percent: f32,
+    /// Anzahl bereits erfolgter Versuche bei automatischem Retry (siehe
+    /// `JobOptions::max_retries`). `0` beim ersten Lauf.
+    pub attempt: u32,
+    /// Dispatch-Prioritaet (siehe `JobOptions::priority`): hoeher = dringender.
+    /// Wird bei `JobCommand::SetPriority` aktualisiert, solange der Job noch
+    /// `Queued` ist.
+    pub priority: u8,
     pub cancel_token: CancellationToken,
 }
 
@@ -45,6 +172,7 @@ impl Job {
         mode: JobMode,
         options: JobOptions,
     ) -> Self {
+        let priority = options.priority;
         Self {
             id,
             input_path: PathBuf::from(input_path),
@@ -53,6 +181,8 @@ impl Job {
             options,
             status: JobState::Queued,
             percent: 0.0,
+            attempt: 0,
+            priority,
             cancel_token: CancellationToken::new(), // Wird spaeter durch child_token ersetzt
         }
     }
@@ -64,7 +194,13 @@ impl Job {
     }
 
     /// Generiert den Output-Pfad basierend auf Modus und Benennungsoptionen.
+    /// Bei `JobMode::Stream` ist das die Playlist innerhalb von `stream_dir()`
+    /// (Segmente/Init-Segment liegen daneben, siehe dort).
     pub fn output_path(&self) -> PathBuf {
+        if matches!(self.mode, JobMode::Stream) {
+            return self.stream_dir().join("playlist.m3u8");
+        }
+
         let stem = self
             .input_path
             .file_stem()
@@ -74,10 +210,11 @@ impl Job {
         let suffix = &self.options.output_suffix;
 
         let ext = match self.mode {
-            JobMode::ReWrap => "mov",
+            JobMode::ReWrap | JobMode::Concat => "mov",
             JobMode::Proxy | JobMode::BrawProxy | JobMode::R3dProxy => {
                 if self.options.proxy_codec == "av1" { "mp4" } else { "mov" }
             }
+            JobMode::Stream => unreachable!("oben bereits behandelt"),
         };
 
         let output_dir = if self.options.output_subfolder.is_empty() {
@@ -89,43 +226,173 @@ impl Job {
         output_dir.join(format!("{stem}{suffix}.{ext}"))
     }
 
-    pub fn to_status(&self) -> JobStatus {
+    /// Eigenes Verzeichnis fuer `JobMode::Stream`: Playlist, Init-Segment (bei
+    /// `stream_format == "fmp4"`) und Media-Segmente liegen hier nebeneinander,
+    /// benannt wie ein normaler Output (Stamm + Suffix), aber als Ordner statt
+    /// als Einzeldatei.
+    pub fn stream_dir(&self) -> PathBuf {
+        let stem = self
+            .input_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let suffix = &self.options.output_suffix;
+
+        let output_dir = if self.options.output_subfolder.is_empty() {
+            self.output_dir.clone()
+        } else {
+            self.output_dir.join(&self.options.output_subfolder)
+        };
+
+        output_dir.join(format!("{stem}{suffix}"))
+    }
+
+    /// `queue_position` ist nur fuer `Queued`-Jobs gesetzt (1-basiert, je Lane,
+    /// siehe `JobCommand::GetStatus`), `lane` nur fuer `Running`-/`Paused`-Jobs.
+    pub fn to_status(&self, queue_position: Option<usize>, lane: Option<Lane>) -> JobStatus {
         JobStatus {
             id: self.id.clone(),
             input_path: self.input_path.to_string_lossy().to_string(),
             mode: self.mode.clone(),
             status: self.status.clone(),
             percent: self.percent,
+            queue_position,
+            lane,
         }
     }
 }
 
+/// Ein Eintrag der zentralen, prioritaets-geordneten Ready-Queue (siehe
+/// `run_queue`/`try_dispatch`). Hoehere `priority` wird zuerst dispatcht; bei
+/// Gleichstand gewinnt die kleinere `seq` (frueher angekommen zuerst, FIFO).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReadyEntry {
+    priority: u8,
+    seq: u64,
+    job_id: String,
+}
+
+impl Ord for ReadyEntry {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for ReadyEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ein fuer den Start vorbereiteter Job: Pfade validiert, BRAW/R3D-Metadaten
+/// geprobt, FFmpeg-Args gebaut. Liegt in `run_queue`s `pending`-Map bis
+/// `try_dispatch` genug Tokens fuer ihn findet und ihn tatsaechlich startet.
+struct PreparedJob {
+    job_id: String,
+    job_input_path: PathBuf,
+    job_output_dir: PathBuf,
+    job_options: JobOptions,
+    job_mode: JobMode,
+    job_attempt: u32,
+    is_braw: bool,
+    is_r3d: bool,
+    is_chunked: bool,
+    is_concat: bool,
+    is_stream: bool,
+    is_two_pass: bool,
+    braw_meta: Option<braw_runner::BrawMetadata>,
+    r3d_meta: Option<r3d_runner::R3dMetadata>,
+    total_duration_us: i64,
+    args: Vec<String>,
+    output_path: PathBuf,
+    /// Nur fuer `is_stream` und `is_two_pass`: Encoder-Parameter fuer
+    /// `segmented::build_segment_args` bzw. `runner::run_two_pass_ffmpeg` (werden,
+    /// anders als bei normalen Proxy-Jobs, erst im Spawn-Task selbst in
+    /// FFmpeg-Argumente uebersetzt statt vorab via `build_ffmpeg_args`).
+    nvenc_full_gpu: bool,
+    crf_override: Option<u32>,
+    color_meta: runner::ColorMetadata,
+    cancel_token: CancellationToken,
+    pid_set: PidSet,
+}
+
 /// Kommandos die an die JobQueue geschickt werden koennen.
 pub enum JobCommand {
     Add(Job),
     Cancel(String),
-    SetMaxParallel(usize),
+    /// Setzt die Kapazitaet der CPU-Lane neu (siehe `job_lane`).
+    SetMaxParallelCpu(usize),
+    /// Setzt die Kapazitaet der GPU-Lane neu (siehe `job_lane`).
+    SetMaxParallelGpu(usize),
+    /// Setzt die Dispatch-Prioritaet eines noch `Queued`-Jobs neu (siehe
+    /// `Job::priority`) und sortiert die Ready-Queue entsprechend um. Ohne
+    /// Effekt falls der Job bereits laeuft oder nicht mehr existiert.
+    SetPriority(String, u8),
     PauseAll,
     ResumeAll,
+    /// Pausiert einen einzelnen laufenden Job per `SIGSTOP` (siehe
+    /// `Request::PauseJob`), im Gegensatz zu `PauseAll` unabhaengig vom
+    /// globalen `is_paused`-Flag des Schedulers.
+    PauseJob(String),
+    /// Setzt einen per `PauseJob` angehaltenen Job per `SIGCONT` fort.
+    ResumeJob(String),
+    /// Kooperatives Drain (siehe `DrainStatus`): keine neuen Jobs mehr starten,
+    /// noch wartende `Queued`-Jobs abbrechen, laufende `Running`-Jobs aber in Ruhe
+    /// zu Ende laufen lassen. Der harte `shutdown_token`-Cancel bleibt davon
+    /// unberuehrt als separate Force-Quit-Eskalation.
+    Drain,
+    /// Internes Kommando: ein zuvor dispatchter Job ist beendet (egal ob Done,
+    /// Error, Cancelled oder Retry) und gibt seine Token-Kosten in der
+    /// angegebenen Lane zurueck. Wird ausschliesslich von `dispatch_job` selbst
+    /// ueber den `retry_tx`-Sender verschickt, niemals von aussen (IPC).
+    JobFinished(Lane, usize),
     GetStatus(tokio::sync::oneshot::Sender<Vec<JobStatus>>),
 }
 
+/// Fortschritt eines laufenden Drains, ueber einen `watch`-Channel verteilt
+/// (siehe `JobQueue::drain_status`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainStatus {
+    /// Kein Drain angefordert.
+    Idle,
+    /// Drain laeuft, `remaining` Jobs sind noch `Running`.
+    Draining { remaining: usize },
+    /// Drain abgeschlossen, alle Jobs sind beendet.
+    Complete,
+}
+
 /// Die zentrale Job-Queue.
 pub struct JobQueue {
     cmd_tx: mpsc::Sender<JobCommand>,
     shutdown_token: CancellationToken,
+    drain_tx: watch::Sender<DrainStatus>,
 }
 
 impl JobQueue {
     /// Erstellt eine neue JobQueue und gibt (queue, event_receiver) zurueck.
-    /// `max_parallel` bestimmt wie viele Jobs gleichzeitig laufen duerfen.
+    /// `_max_parallel` wird nicht ausgewertet: die Lane-Kapazitaeten erhaelt
+    /// `run_queue` als `max_parallel_cpu`/`max_parallel_gpu`. Liest
+    /// beim Start `store` ein und reiht alle noch offenen Jobs (Queued/Running)
+    /// wieder ein, damit ein Absturz/Neustart keine Arbeit stillschweigend verliert.
     pub fn new(
         _max_parallel: usize,
         _response_tx: mpsc::Sender<Response>,
+        store: &JobStore,
     ) -> (Self, mpsc::Receiver<JobCommand>) {
         let (cmd_tx, cmd_rx) = mpsc::channel(256);
         let shutdown_token = CancellationToken::new();
-        (Self { cmd_tx, shutdown_token }, cmd_rx)
+        let (drain_tx, _drain_rx) = watch::channel(DrainStatus::Idle);
+
+        for job in store.load_recoverable() {
+            // Bestbemueht: der Channel ist frisch und ausreichend gepuffert (256),
+            // ein voller Puffer hier waere ein Vorzeichen fuer ein groesseres Problem.
+            let _ = cmd_tx.try_send(JobCommand::Add(job));
+        }
+
+        (Self { cmd_tx, shutdown_token, drain_tx }, cmd_rx)
     }
 
     /// Gibt das Shutdown-Token zurueck, um es beim Herunterfahren zu cancellen.
@@ -133,6 +400,31 @@ impl JobQueue {
         self.shutdown_token.clone()
     }
 
+    /// Gibt einen weiteren Sender auf den Kommando-Channel zurueck. Wird von
+    /// `run_queue` selbst benutzt, um fehlgeschlagene Jobs nach Backoff-Delay
+    /// als neuen `JobCommand::Add` wieder einzureihen (siehe `retry_delay_ms`)
+    /// und um `JobCommand::JobFinished` an sich selbst zu schicken.
+    pub fn cmd_sender(&self) -> mpsc::Sender<JobCommand> {
+        self.cmd_tx.clone()
+    }
+
+    /// Gibt einen Klon des Drain-Status-Senders zurueck. Wird von `run_queue`
+    /// selbst benutzt, um den Drain-Fortschritt zu broadcasten.
+    pub fn drain_sender(&self) -> watch::Sender<DrainStatus> {
+        self.drain_tx.clone()
+    }
+
+    /// Abonniert den Drain-Status, um z.B. "wartet auf N Jobs" anzuzeigen.
+    pub fn drain_status(&self) -> watch::Receiver<DrainStatus> {
+        self.drain_tx.subscribe()
+    }
+
+    /// Startet ein kooperatives Drain (siehe `JobCommand::Drain`).
+    pub async fn drain(&self) -> Result<()> {
+        self.cmd_tx.send(JobCommand::Drain).await?;
+        Ok(())
+    }
+
     pub async fn add_job(&self, job: Job) -> Result<()> {
         self.cmd_tx.send(JobCommand::Add(job)).await?;
         Ok(())
@@ -143,8 +435,21 @@ impl JobQueue {
         Ok(())
     }
 
-    pub async fn set_max_parallel(&self, n: usize) -> Result<()> {
-        self.cmd_tx.send(JobCommand::SetMaxParallel(n.max(1))).await?;
+    /// Setzt die Kapazitaet der CPU-Lane neu (siehe `job_lane`).
+    pub async fn set_max_parallel_cpu(&self, n: usize) -> Result<()> {
+        self.cmd_tx.send(JobCommand::SetMaxParallelCpu(n.max(1))).await?;
+        Ok(())
+    }
+
+    /// Setzt die Kapazitaet der GPU-Lane neu (siehe `job_lane`).
+    pub async fn set_max_parallel_gpu(&self, n: usize) -> Result<()> {
+        self.cmd_tx.send(JobCommand::SetMaxParallelGpu(n.max(1))).await?;
+        Ok(())
+    }
+
+    /// Setzt die Prioritaet eines noch wartenden Jobs neu (siehe `JobCommand::SetPriority`).
+    pub async fn set_priority(&self, id: String, priority: u8) -> Result<()> {
+        self.cmd_tx.send(JobCommand::SetPriority(id, priority)).await?;
         Ok(())
     }
 
@@ -158,6 +463,16 @@ impl JobQueue {
         Ok(())
     }
 
+    pub async fn pause_job(&self, id: String) -> Result<()> {
+        self.cmd_tx.send(JobCommand::PauseJob(id)).await?;
+        Ok(())
+    }
+
+    pub async fn resume_job(&self, id: String) -> Result<()> {
+        self.cmd_tx.send(JobCommand::ResumeJob(id)).await?;
+        Ok(())
+    }
+
     pub async fn get_status(&self) -> Result<Vec<JobStatus>> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.cmd_tx.send(JobCommand::GetStatus(tx)).await?;
@@ -165,38 +480,721 @@ impl JobQueue {
     }
 }
 
-/// Laeuft als eigener Task und verarbeitet Job-Kommandos.
+/// Entfernt einen fehlgeschlagenen Job aus Laufzeit-Map und Store, meldet
+/// `Response::JobRetrying` und reiht ihn nach dem Backoff-Delay mit erhoehtem
+/// `attempt`-Zaehler ueber `retry_tx` wieder ein (als frischer `JobCommand::Add`,
+/// durchlaeuft also erneut Pfadvalidierung, Probing und Dispatch-Einreihung).
+///
+/// Bei `FailureClass::RecoverableHardware` und einem bereits zuvor gescheiterten
+/// HW-Versuch (`attempt >= 1`) wird der naechste Versuch auf Software-Encoding
+/// degradiert (`hw_accel` → `"none"`), statt erneut gegen dieselbe erschoepfte
+/// Hardware-Ressource anzurennen (siehe `ffmpeg::retry::FailureClass`).
+#[allow(clippy::too_many_arguments)]
+async fn requeue_for_retry(
+    jobs: &Arc<RwLock<HashMap<String, Job>>>,
+    store: &JobStore,
+    resp_tx: &mpsc::Sender<Response>,
+    retry_tx: &mpsc::Sender<JobCommand>,
+    id: String,
+    attempt: u32,
+    input_path: &Path,
+    output_dir: &Path,
+    mode: &JobMode,
+    options: &JobOptions,
+    failure_class: FailureClass,
+) {
+    {
+        let mut map = jobs.write().await;
+        map.remove(&id);
+    }
+    let _ = store.remove(&id);
+
+    let next_attempt = attempt + 1;
+    let delay_ms = retry_delay_ms(options.retry_base_delay_ms, next_attempt);
+    let _ = resp_tx
+        .send(Response::JobRetrying {
+            id: id.clone(),
+            attempt: next_attempt,
+            delay_ms,
+        })
+        .await;
+
+    let mut retry_options = options.clone();
+    if failure_class == FailureClass::RecoverableHardware
+        && attempt >= 1
+        && retry_options.hw_accel != "none"
+    {
+        eprintln!(
+            "Job {id}: wiederholter HW-Encode-Fehler, degradiere auf Software-Encoding fuer Versuch {next_attempt}"
+        );
+        retry_options.hw_accel = "none".to_string();
+    }
+
+    let mut retry_job = Job::new(
+        id,
+        input_path.to_string_lossy().to_string(),
+        output_dir.to_string_lossy().to_string(),
+        mode.clone(),
+        retry_options,
+    );
+    retry_job.attempt = next_attempt;
+
+    let retry_tx = retry_tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+        let _ = retry_tx.send(JobCommand::Add(retry_job)).await;
+    });
+}
+
+/// Versucht, so viele Jobs wie moeglich aus der Ready-Queue **einer** Lane zu
+/// dispatchen (siehe `dispatch_ready` fuer den Aufruf fuer beide Lanen).
+/// Schaut dabei **ausschliesslich** auf den Kopf der Heap (strikte Prioritaets-
+/// Reihenfolge): passt dessen (auf die Gesamtkapazitaet gedeckelter) Kostenwert
+/// nicht mehr ins freie Budget, wird abgebrochen statt einen guenstigeren, aber
+/// niedriger priorisierten Job dahinter vorzulassen. Das ist die eigentliche
+/// "deterministische, nutzer-kontrollierbare" Eigenschaft dieses Schedulers.
+/// Da jede Lane ihre eigene Heap und ihr eigenes Budget hat, blockiert ein an
+/// der GPU-Kapazitaet wartender Job nie die CPU-Lane und umgekehrt.
+#[allow(clippy::too_many_arguments)]
+async fn try_dispatch(
+    lane: Lane,
+    ready_heap: &mut BinaryHeap<ReadyEntry>,
+    pending: &mut HashMap<String, PreparedJob>,
+    capacity: usize,
+    in_use: &mut usize,
+    running_count: &mut usize,
+    is_paused: bool,
+    jobs: &Arc<RwLock<HashMap<String, Job>>>,
+    store: &JobStore,
+    response_tx: &mpsc::Sender<Response>,
+    ffmpeg_pids: &Arc<RwLock<HashMap<String, PidSet>>>,
+    retry_tx: &mpsc::Sender<JobCommand>,
+) {
+    if is_paused {
+        return;
+    }
+    loop {
+        let Some(top) = ready_heap.peek() else {
+            break;
+        };
+        let job_id = top.job_id.clone();
+        let Some(prepared) = pending.get(&job_id) else {
+            // Veralteter Eintrag: der Job wurde zwischenzeitlich dispatcht,
+            // abgebrochen oder per SetPriority neu eingereiht – verwerfen.
+            ready_heap.pop();
+            continue;
+        };
+        let capacity_now = capacity.max(1);
+        // Ein Job dessen Kosten die Gesamtkapazitaet uebersteigen wird auf die
+        // volle Kapazitaet gedeckelt, damit er trotzdem allein laufen kann statt
+        // fuer immer zu blockieren. Die GPU-Lane zaehlt schlicht Sessions (siehe
+        // `job_cost`), dort ist der Kostenwert immer 1.
+        let cost = match lane {
+            Lane::Gpu => 1,
+            Lane::Cpu => job_cost(&prepared.job_mode),
+        }
+        .min(capacity_now);
+        if capacity_now.saturating_sub(*in_use) < cost {
+            break;
+        }
+        ready_heap.pop();
+        let prepared = pending.remove(&job_id).expect("soeben per peek geprueft");
+        *in_use += cost;
+        *running_count += 1;
+        dispatch_job(prepared, cost, lane, jobs, store, response_tx, ffmpeg_pids, retry_tx).await;
+    }
+}
+
+/// Ruft `try_dispatch` fuer beide Lanen auf (siehe `job_lane`): jede Lane hat
+/// ihre eigene Ready-Queue und ihr eigenes Token-Budget.
+#[allow(clippy::too_many_arguments)]
+async fn dispatch_ready(
+    ready_heap_cpu: &mut BinaryHeap<ReadyEntry>,
+    ready_heap_gpu: &mut BinaryHeap<ReadyEntry>,
+    pending: &mut HashMap<String, PreparedJob>,
+    cpu_capacity: usize,
+    cpu_in_use: &mut usize,
+    gpu_capacity: usize,
+    gpu_in_use: &mut usize,
+    running_count: &mut usize,
+    is_paused: bool,
+    jobs: &Arc<RwLock<HashMap<String, Job>>>,
+    store: &JobStore,
+    response_tx: &mpsc::Sender<Response>,
+    ffmpeg_pids: &Arc<RwLock<HashMap<String, PidSet>>>,
+    retry_tx: &mpsc::Sender<JobCommand>,
+) {
+    try_dispatch(
+        Lane::Cpu, ready_heap_cpu, pending, cpu_capacity, cpu_in_use, running_count,
+        is_paused, jobs, store, response_tx, ffmpeg_pids, retry_tx,
+    )
+    .await;
+    try_dispatch(
+        Lane::Gpu, ready_heap_gpu, pending, gpu_capacity, gpu_in_use, running_count,
+        is_paused, jobs, store, response_tx, ffmpeg_pids, retry_tx,
+    )
+    .await;
+}
+
+/// Startet einen zuvor von `try_dispatch` freigegebenen Job tatsaechlich:
+/// Status auf `Running`, eigener Task fuer BRAW/R3D/Chunked/Concat/FFmpeg,
+/// Event-Weiterleitung an IPC, und am Ende `JobCommand::JobFinished` um die
+/// Tokens an den zentralen Scheduler zurueckzugeben.
+#[allow(clippy::too_many_arguments)]
+async fn dispatch_job(
+    prepared: PreparedJob,
+    cost: usize,
+    lane: Lane,
+    jobs: &Arc<RwLock<HashMap<String, Job>>>,
+    store: &JobStore,
+    response_tx: &mpsc::Sender<Response>,
+    ffmpeg_pids: &Arc<RwLock<HashMap<String, PidSet>>>,
+    retry_tx: &mpsc::Sender<JobCommand>,
+) {
+    let PreparedJob {
+        job_id,
+        job_input_path,
+        job_output_dir,
+        job_options,
+        job_mode,
+        job_attempt,
+        is_braw,
+        is_r3d,
+        is_chunked,
+        is_concat,
+        is_stream,
+        is_two_pass,
+        braw_meta,
+        r3d_meta,
+        total_duration_us,
+        args,
+        output_path,
+        nvenc_full_gpu,
+        crf_override,
+        color_meta,
+        cancel_token,
+        pid_set,
+    } = prepared;
+
+    // Status auf Running setzen
+    {
+        let mut map = jobs.write().await;
+        if let Some(j) = map.get_mut(&job_id) {
+            j.status = JobState::Running;
+            let _ = store.put(j);
+        }
+    }
+
+    // Eigene Kopien fuer einen eventuellen Retry (die Originale werden je nach
+    // Task-Zweig weiter unten in tokio::spawn hineingezogen).
+    let job_input_path_retry = job_input_path.clone();
+    let job_output_dir_retry = job_output_dir.clone();
+    let job_options_retry = job_options.clone();
+    let job_mode_retry = job_mode.clone();
+
+    // Eigene Kopie des Output-Pfads fuer den optionalen Upload-Schritt nach
+    // `FfmpegEvent::Done` (das Original wird weiter unten in den Task-Zweig
+    // hineingezogen).
+    let output_path_for_upload = output_path.clone();
+
+    // Event-Channel fuer diesen Job-Lauf
+    let (event_tx, mut event_rx) = mpsc::channel::<FfmpegEvent>(64);
+
+    let jobs_ref = jobs.clone();
+    let store_ref = store.clone();
+    let resp_tx = response_tx.clone();
+    let ffmpeg_pids_ref = ffmpeg_pids.clone();
+    let retry_tx_ref = retry_tx.clone();
+    let job_id_for_monitor = job_id.clone();
+    let pid_set_for_task = pid_set;
+
+    let handle = tokio::spawn(async move {
+        // Job in eigenem Task starten (BRAW, R3D, Chunked oder FFmpeg)
+        let task_id = job_id.clone();
+        let task_handle = if is_braw {
+            // Zwei getrennte OS-Prozesse pro Job (braw-bridge + FFmpeg, siehe
+            // `braw::runner::run_braw_job`) – je ein eigener Slot, damit
+            // Pause/Resume (`PidSet::signal_all`) wirklich beide suspendiert.
+            let bridge_pid_slot = pid_set_for_task.register().await;
+            let ffmpeg_pid_slot = pid_set_for_task.register().await;
+            let meta = braw_meta.unwrap(); // sicher: is_braw → braw_meta = Some
+            tokio::spawn(async move {
+                braw_runner::run_braw_job(
+                    task_id,
+                    job_input_path,
+                    output_path,
+                    &job_options,
+                    meta,
+                    event_tx,
+                    cancel_token,
+                    bridge_pid_slot,
+                    ffmpeg_pid_slot,
+                )
+                .await
+            })
+        } else if is_r3d {
+            // Zwei getrennte OS-Prozesse pro Job (r3d-bridge + FFmpeg, siehe
+            // `r3d::runner::run_r3d_job`) – je ein eigener Slot, damit
+            // Pause/Resume (`PidSet::signal_all`) wirklich beide suspendiert.
+            let bridge_pid_slot = pid_set_for_task.register().await;
+            let ffmpeg_pid_slot = pid_set_for_task.register().await;
+            let meta = r3d_meta.unwrap(); // sicher: is_r3d → r3d_meta = Some
+            tokio::spawn(async move {
+                r3d_runner::run_r3d_job(
+                    task_id,
+                    job_input_path,
+                    output_path,
+                    &job_options,
+                    meta,
+                    event_tx,
+                    cancel_token,
+                    bridge_pid_slot,
+                    ffmpeg_pid_slot,
+                )
+                .await
+            })
+        } else if is_chunked {
+            tokio::spawn(async move {
+                chunked::run_chunked_job(
+                    task_id,
+                    job_input_path,
+                    output_path,
+                    job_mode,
+                    job_options,
+                    total_duration_us,
+                    event_tx,
+                    cancel_token,
+                    pid_set_for_task,
+                )
+                .await
+            })
+        } else if is_concat {
+            let pid_slot = pid_set_for_task.register().await;
+            tokio::spawn(async move {
+                concat::run_concat_job(
+                    task_id,
+                    job_input_path,
+                    output_path,
+                    job_options,
+                    event_tx,
+                    cancel_token,
+                    pid_slot,
+                )
+                .await
+            })
+        } else if is_stream {
+            let pid_slot = pid_set_for_task.register().await;
+            // `output_path` ist bei Stream-Jobs die Playlist-Datei innerhalb des
+            // Stream-Verzeichnisses (siehe `Job::stream_dir`/`Job::output_path`).
+            let stream_dir = output_path
+                .parent()
+                .expect("Stream-Output-Pfad hat immer ein Elternverzeichnis")
+                .to_path_buf();
+            tokio::spawn(async move {
+                segmented::run_segmented_job(
+                    task_id,
+                    job_input_path,
+                    stream_dir,
+                    job_options,
+                    nvenc_full_gpu,
+                    crf_override,
+                    color_meta,
+                    total_duration_us,
+                    event_tx,
+                    cancel_token,
+                    pid_slot,
+                )
+                .await
+            })
+        } else if is_two_pass {
+            let pid_slot = pid_set_for_task.register().await;
+            let paused_rx = pid_set_for_task.paused_rx();
+            let process_timeout_secs = job_options.process_timeout_secs;
+            let progress_via_tcp = job_options.progress_via_tcp;
+            tokio::spawn(async move {
+                runner::run_two_pass_ffmpeg(
+                    task_id,
+                    job_input_path,
+                    output_path,
+                    job_mode,
+                    job_options,
+                    nvenc_full_gpu,
+                    crf_override,
+                    color_meta,
+                    total_duration_us,
+                    process_timeout_secs,
+                    progress_via_tcp,
+                    event_tx,
+                    cancel_token,
+                    pid_slot,
+                    paused_rx,
+                )
+                .await
+            })
+        } else {
+            let pid_slot = pid_set_for_task.register().await;
+            let paused_rx = pid_set_for_task.paused_rx();
+            let process_timeout_secs = job_options.process_timeout_secs;
+            let progress_via_tcp = job_options.progress_via_tcp;
+            // Netzwerk-Sink ist nur fuer normale Proxy-Jobs sinnvoll (siehe
+            // `build_ffmpeg_args`), ReWrap schreibt immer lokal.
+            let network_sink = if matches!(job_mode, JobMode::Proxy) {
+                network_sink::parse_output_url(&job_options)
+            } else {
+                Ok(None)
+            };
+            tokio::spawn(async move {
+                let network_sink = match network_sink {
+                    Ok(sink) => sink,
+                    Err(e) => {
+                        let _ = event_tx
+                            .send(FfmpegEvent::Error { id: task_id, message: format!("output_url: {e}") })
+                            .await;
+                        return Ok(());
+                    }
+                };
+                runner::run_ffmpeg(
+                    task_id,
+                    args,
+                    &output_path,
+                    total_duration_us,
+                    process_timeout_secs,
+                    progress_via_tcp,
+                    network_sink,
+                    event_tx,
+                    cancel_token,
+                    pid_slot,
+                    paused_rx,
+                )
+                .await
+            })
+        };
+
+        // Events weiterleiten an IPC
+        while let Some(event) = event_rx.recv().await {
+            match event {
+                FfmpegEvent::Progress {
+                    id,
+                    percent,
+                    fps,
+                    speed,
+                    frame,
+                    elapsed_us,
+                } => {
+                    {
+                        let mut map = jobs_ref.write().await;
+                        if let Some(j) = map.get_mut(&id) {
+                            j.percent = percent;
+                            let _ = store_ref.put(j);
+                        }
+                    }
+                    let _ = resp_tx
+                        .send(Response::JobProgress {
+                            id,
+                            percent,
+                            fps,
+                            speed,
+                            frame,
+                            elapsed_us,
+                        })
+                        .await;
+                }
+                FfmpegEvent::Done { id } => {
+                    // Optionaler Upload-Schritt vor dem eigentlichen `JobDone`
+                    // (siehe `JobOptions::upload_destination`).
+                    let upload_result = match &job_options_retry.upload_destination {
+                        Some(dest) => {
+                            Some(crate::jobs::upload::upload_output(&id, &output_path_for_upload, dest, &resp_tx).await)
+                        }
+                        None => None,
+                    };
+                    match upload_result {
+                        Some(Err(e)) => {
+                            {
+                                let mut map = jobs_ref.write().await;
+                                if let Some(j) = map.get_mut(&id) {
+                                    j.status = JobState::Error;
+                                }
+                            }
+                            let _ = store_ref.remove(&id);
+                            let _ = resp_tx
+                                .send(Response::JobError {
+                                    id,
+                                    message: format!("Upload fehlgeschlagen: {e}"),
+                                })
+                                .await;
+                        }
+                        uploaded => {
+                            {
+                                let mut map = jobs_ref.write().await;
+                                if let Some(j) = map.get_mut(&id) {
+                                    j.status = JobState::Done;
+                                    j.percent = 100.0;
+                                }
+                            }
+                            let _ = store_ref.remove(&id);
+                            if let Some(Ok(url)) = uploaded {
+                                let _ = resp_tx
+                                    .send(Response::JobUploaded { id: id.clone(), url })
+                                    .await;
+                            }
+                            let _ = resp_tx.send(Response::JobDone { id }).await;
+                        }
+                    }
+                }
+                FfmpegEvent::Error { id, message } => {
+                    let failure_class = classify_failure(&message);
+                    if failure_class != FailureClass::Fatal && job_attempt < job_options_retry.max_retries {
+                        requeue_for_retry(
+                            &jobs_ref,
+                            &store_ref,
+                            &resp_tx,
+                            &retry_tx_ref,
+                            id,
+                            job_attempt,
+                            &job_input_path_retry,
+                            &job_output_dir_retry,
+                            &job_mode_retry,
+                            &job_options_retry,
+                            failure_class,
+                        )
+                        .await;
+                    } else {
+                        {
+                            let mut map = jobs_ref.write().await;
+                            if let Some(j) = map.get_mut(&id) {
+                                j.status = JobState::Error;
+                            }
+                        }
+                        let _ = store_ref.remove(&id);
+                        let classification = if failure_class == FailureClass::Fatal {
+                            FailureOutcome::Fatal
+                        } else {
+                            FailureOutcome::RetriesExhausted
+                        };
+                        let _ = resp_tx
+                            .send(Response::JobFailedFinal {
+                                id,
+                                attempts: job_attempt,
+                                classification,
+                                message,
+                            })
+                            .await;
+                    }
+                }
+                FfmpegEvent::Cancelled { id } => {
+                    {
+                        let mut map = jobs_ref.write().await;
+                        if let Some(j) = map.get_mut(&id) {
+                            j.status = JobState::Cancelled;
+                        }
+                    }
+                    let _ = store_ref.remove(&id);
+                    let _ = resp_tx
+                        .send(Response::JobCancelled { id })
+                        .await;
+                }
+                FfmpegEvent::SegmentReady { id, path, index } => {
+                    // Kein terminales Event: Job bleibt `Running`, es folgen weitere
+                    // Segmente bzw. abschliessend Done/Error/Cancelled.
+                    let _ = resp_tx
+                        .send(Response::JobSegmentReady {
+                            id,
+                            segment_path: path,
+                            index,
+                        })
+                        .await;
+                }
+                FfmpegEvent::Uploaded { id, url } => {
+                    // Netzwerk-Sink (siehe `JobOptions::output_url`) hat den Proxy
+                    // erfolgreich gestreamt; geht unmittelbar dem `Done` fuer denselben
+                    // Job voraus (siehe `ffmpeg::runner::run_ffmpeg`).
+                    let _ = resp_tx.send(Response::JobUploaded { id, url }).await;
+                }
+            }
+        }
+
+        let task_label = if is_braw { "braw-bridge" } else if is_r3d { "r3d-bridge" } else if is_chunked { "Chunked-Encode" } else if is_concat { "Concat-Transition" } else if is_stream { "Stream-Segmente" } else if is_two_pass { "Zwei-Pass-Encode" } else { "FFmpeg" };
+        match task_handle.await {
+            Ok(Ok(())) => {}  // Normale Beendigung: terminales Event wurde bereits gesendet
+            Ok(Err(e)) => {
+                if job_attempt < job_options_retry.max_retries {
+                    requeue_for_retry(
+                        &jobs_ref,
+                        &store_ref,
+                        &resp_tx,
+                        &retry_tx_ref,
+                        job_id.clone(),
+                        job_attempt,
+                        &job_input_path_retry,
+                        &job_output_dir_retry,
+                        &job_mode_retry,
+                        &job_options_retry,
+                        FailureClass::Recoverable,
+                    )
+                    .await;
+                } else {
+                    let _ = resp_tx.send(Response::JobError {
+                        id: job_id.clone(),
+                        message: format!("{task_label} konnte nicht ausgefuehrt werden: {e}"),
+                    }).await;
+                }
+            }
+            Err(e) => {
+                if job_attempt < job_options_retry.max_retries {
+                    requeue_for_retry(
+                        &jobs_ref,
+                        &store_ref,
+                        &resp_tx,
+                        &retry_tx_ref,
+                        job_id.clone(),
+                        job_attempt,
+                        &job_input_path_retry,
+                        &job_output_dir_retry,
+                        &job_mode_retry,
+                        &job_options_retry,
+                        FailureClass::Recoverable,
+                    )
+                    .await;
+                } else {
+                    let _ = resp_tx.send(Response::JobError {
+                        id: job_id.clone(),
+                        message: format!("{task_label}-Task Panik: {e}"),
+                    }).await;
+                }
+            }
+        }
+
+        // PID-Eintrag und Job aus HashMaps entfernen
+        ffmpeg_pids_ref.write().await.remove(&job_id);
+        jobs_ref.write().await.remove(&job_id);
+        let _ = store_ref.remove(&job_id);
+
+        // Tokens freigeben: ueber den Kommando-Channel an den zentralen
+        // Scheduler zurueckmelden statt geteilten Zustand direkt zu mutieren –
+        // der lebt jetzt ausschliesslich innerhalb von `run_queue`.
+        let _ = retry_tx_ref.send(JobCommand::JobFinished(lane, cost)).await;
+    });
+
+    // Monitor: wenn der Job-Task panikt → JobError an Python senden
+    let monitor_tx = response_tx.clone();
+    let monitor_id = job_id_for_monitor;
+    tokio::spawn(async move {
+        if let Err(e) = handle.await {
+            let _ = monitor_tx.send(Response::JobError {
+                id: monitor_id,
+                message: format!("Job-Task-Panik: {e}"),
+            }).await;
+        }
+    });
+}
+
+/// Laeuft als eigener Task und verarbeitet Job-Kommandos. Saemtlicher
+/// Scheduler-Zustand (Ready-Queues, Token-Budgets, Pause/Drain-Flags) lebt als
+/// einfache lokale Variablen in dieser Funktion – da alle Kommandos sequenziell
+/// ueber denselben Channel verarbeitet werden, braucht es weder Atomics noch
+/// einen zusaetzlichen Wake-Mechanismus; jeder Dispatch-relevante Zustandswechsel
+/// ruft direkt `dispatch_ready` auf.
+/// `max_parallel_cpu`/`max_parallel_gpu` sind die Start-Kapazitaeten der
+/// getrennten Lanes (siehe `job_lane`); `--max-parallel-cpu` defaultet auf
+/// `std::thread::available_parallelism()`, `--max-parallel-gpu` konservativ auf 2
+/// (siehe `main`).
+/// `retry_tx` ist ein weiterer Sender auf den eigenen Kommando-Channel, ueber
+/// den fehlgeschlagene Jobs nach Backoff-Delay als `JobCommand::Add` wieder
+/// eingereiht werden (siehe `retry_delay_ms`) und dispatchte Jobs ihr
+/// `JobCommand::JobFinished` senden.
 pub async fn run_queue(
     mut cmd_rx: mpsc::Receiver<JobCommand>,
-    max_parallel: usize,
+    max_parallel_cpu: usize,
+    max_parallel_gpu: usize,
     response_tx: mpsc::Sender<Response>,
     shutdown_token: CancellationToken,
+    store: JobStore,
+    retry_tx: mpsc::Sender<JobCommand>,
+    drain_tx: watch::Sender<DrainStatus>,
+    hw_caps: Arc<HwCapabilities>,
 ) {
-    let limit = Arc::new(AtomicUsize::new(max_parallel.max(1)));
-    let running = Arc::new(AtomicUsize::new(0));
-    let slot_free = Arc::new(Notify::new());
-    let is_paused = Arc::new(AtomicBool::new(false));
-    // job_id → PID des laufenden FFmpeg-Prozesses (0 = noch nicht gestartet)
-    let ffmpeg_pids: Arc<RwLock<HashMap<String, Arc<AtomicU32>>>> =
+    // Token-Budget-Scheduler pro Lane: jeder Job kostet `job_cost` (CPU) bzw.
+    // immer 1 (GPU) Tokens innerhalb seiner Lane (siehe `job_lane`/`try_dispatch`).
+    // `*_capacity` ist die per `SetMaxParallelCpu`/`SetMaxParallelGpu` gesetzte
+    // Kapazitaet der jeweiligen Lane, `*_in_use` die Summe der Kosten aktuell
+    // dispatchter Jobs in dieser Lane.
+    let mut cpu_capacity = max_parallel_cpu.max(1);
+    let mut cpu_in_use: usize = 0;
+    let mut gpu_capacity = max_parallel_gpu.max(1);
+    let mut gpu_in_use: usize = 0;
+    // Laufende (dispatchte) Jobs ueber beide Lanen hinweg, unabhaengig vom
+    // Token-Budget – fuer den "noch N Jobs uebrig"-Drain-Fortschritt.
+    let mut running_count: usize = 0;
+    let mut is_paused = false;
+    // Kooperatives Drain (siehe `JobCommand::Drain`): sobald gesetzt, werden
+    // keine neuen Jobs mehr angenommen und noch wartende Jobs verworfen.
+    let mut is_draining = false;
+    // Getrennte Ready-Queues pro Lane: `try_dispatch` schaut je Lane
+    // ausschliesslich auf deren Kopf (strikte Prioritaets-Reihenfolge),
+    // `pending` haelt die dazugehoerigen vorbereiteten Job-Daten fuer beide
+    // Lanen gemeinsam (der Job-Mode/die Optionen darin verraten die Lane, siehe
+    // `job_lane`).
+    let mut ready_heap_cpu: BinaryHeap<ReadyEntry> = BinaryHeap::new();
+    let mut ready_heap_gpu: BinaryHeap<ReadyEntry> = BinaryHeap::new();
+    let mut pending: HashMap<String, PreparedJob> = HashMap::new();
+    let mut next_seq: u64 = 0;
+
+    // job_id → PidSet der laufenden Prozesse (mehrere beim Chunked-Encoding)
+    let ffmpeg_pids: Arc<RwLock<HashMap<String, PidSet>>> =
         Arc::new(RwLock::new(HashMap::new()));
     let jobs: Arc<RwLock<HashMap<String, Job>>> = Arc::new(RwLock::new(HashMap::new()));
 
-    while let Some(cmd) = cmd_rx.recv().await {
+    // `retry_tx` haelt selbst einen Sender auf `cmd_rx` offen (fuer verzoegerte
+    // Retry-Requeues und `JobFinished`), daher wuerde der Channel beim
+    // Herunterfahren nie von selbst schliessen – zusaetzlich explizit auf das
+    // globale Shutdown-Token warten, um trotzdem sauber zu terminieren.
+    loop {
+        let cmd = tokio::select! {
+            cmd = cmd_rx.recv() => match cmd {
+                Some(cmd) => cmd,
+                None => break,
+            },
+            () = shutdown_token.cancelled() => break,
+        };
         match cmd {
             JobCommand::Add(mut job) => {
                 let job_id = job.id.clone();
 
-                // Pfade validieren (Path-Traversal-Schutz)
-                let input_path = match validate_path(&job.input_path) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        let _ = response_tx
-                            .send(Response::JobError {
-                                id: job_id,
-                                message: format!("Ungueltiger Input-Pfad: {e}"),
-                            })
-                            .await;
-                        continue;
+                // Waehrend eines Drains werden keine neuen Jobs mehr angenommen.
+                if is_draining {
+                    let _ = response_tx
+                        .send(Response::JobError {
+                            id: job_id,
+                            message: "Queue wird heruntergefahren (Drain aktiv) – keine neuen Jobs".to_string(),
+                        })
+                        .await;
+                    continue;
+                }
+
+                // Pfade validieren (Path-Traversal-Schutz). RTSP-URLs (siehe
+                // `probe::classify_live_source`) sind keine Dateisystempfade –
+                // `canonicalize()` wuerde hier immer fehlschlagen, also ungeprueft
+                // uebernehmen. V4L2-Capture-Devices sind echte Pfade und durchlaufen
+                // die normale Pruefung.
+                let is_rtsp = matches!(
+                    probe::classify_live_source(&job.input_path),
+                    Some(probe::LiveSourceKind::Rtsp)
+                );
+                let input_path = if is_rtsp {
+                    job.input_path.clone()
+                } else {
+                    match validate_path(&job.input_path) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            let _ = response_tx
+                                .send(Response::JobError {
+                                    id: job_id,
+                                    message: format!("Ungueltiger Input-Pfad: {e}"),
+                                })
+                                .await;
+                            continue;
+                        }
                     }
                 };
                 job.input_path = input_path;
@@ -232,9 +1230,12 @@ pub async fn run_queue(
                 };
                 job.output_dir = output_dir;
 
-                // --- Probing: BRAW / R3D vs. normale Dateien ---
+                // --- Probing: BRAW / R3D / Live-Quelle vs. normale Dateien ---
                 let is_braw = matches!(job.mode, JobMode::BrawProxy);
                 let is_r3d  = matches!(job.mode, JobMode::R3dProxy);
+                // Live-Quelle (RTSP/V4L2, siehe probe::classify_live_source): keine
+                // bekannte Gesamtdauer, ffprobe wuerde haengen bzw. nichts liefern.
+                let is_live = probe::is_live_source(&job.input_path);
                 let input_path_clone = job.input_path.clone();
 
                 // BRAW/R3D: Metadaten via Bridge, sonst ffprobe
@@ -242,6 +1243,7 @@ pub async fn run_queue(
                 let r3d_meta:  Option<r3d_runner::R3dMetadata>;
                 let total_duration_us: i64;
                 let nvenc_full_gpu: bool;
+                let color_meta: runner::ColorMetadata;
 
                 if is_braw {
                     match braw_runner::probe_braw_metadata(&input_path_clone).await {
@@ -253,6 +1255,7 @@ pub async fn run_queue(
                                 0
                             };
                             nvenc_full_gpu = false;
+                            color_meta = runner::ColorMetadata::default();
                             braw_meta = Some(meta);
                             r3d_meta  = None;
                         }
@@ -278,6 +1281,7 @@ pub async fn run_queue(
                                 0
                             };
                             nvenc_full_gpu = false;
+                            color_meta = runner::ColorMetadata::default();
                             r3d_meta  = Some(meta);
                             braw_meta = None;
                         }
@@ -293,14 +1297,50 @@ pub async fn run_queue(
                             continue;
                         }
                     }
+                } else if is_live {
+                    braw_meta = None;
+                    r3d_meta  = None;
+                    // Live-Quelle: keine Gesamtdauer (siehe calculate_progress – `0` wird
+                    // dort bereits als "unbekannt" behandelt), der Job laeuft bis Cancel
+                    // bzw. bis zu `JobOptions::max_duration_secs`/`max_bytes`. Farb-Tags
+                    // gibt es nur, wenn der Nutzer sie explizit setzt (kein ffprobe moeglich).
+                    total_duration_us = 0;
+                    color_meta = runner::ColorMetadata {
+                        color_transfer: job.options.color_transfer.clone().unwrap_or_default(),
+                        color_primaries: job.options.color_primaries.clone().unwrap_or_default(),
+                        color_space: job.options.color_space.clone().unwrap_or_default(),
+                        mastering_display: None,
+                        max_cll: None,
+                        tonemap: job.options.tonemap,
+                    };
+                    let needs_pix_fmt = matches!(job.mode, JobMode::Proxy | JobMode::Stream)
+                        && job.options.hw_accel == "nvenc";
+                    nvenc_full_gpu = if needs_pix_fmt {
+                        // Kein ffprobe auf einer Live-Quelle moeglich – der Nutzer waehlt
+                        // das Capture-Pixel-Format selbst (siehe `JobOptions::capture_pix_fmt`).
+                        match job.options.capture_pix_fmt.as_deref().filter(|s| !s.is_empty()) {
+                            Some(pix_fmt) => {
+                                let codec_for_probe = match job.options.proxy_codec.as_str() {
+                                    "h265" => "hevc_nvenc",
+                                    _ => "h264_nvenc",
+                                };
+                                hw_caps.supports_full_gpu(pix_fmt, codec_for_probe).await
+                            }
+                            None => false,
+                        }
+                    } else {
+                        false
+                    };
                 } else {
                     braw_meta = None;
                     r3d_meta  = None;
-                    // Dauer und Pixel-Format der Quelldatei ermitteln (parallel via ffprobe).
-                    let needs_pix_fmt = matches!(job.mode, JobMode::Proxy)
+                    // Dauer/Framerate/Aufloesung sowie Pixel-Format der Quelldatei ermitteln
+                    // (parallel via ffprobe). `probe_media_metadata` liefert `out_time_us`
+                    // die Referenzdauer fuer `calculate_progress` bei normalen Transcodes.
+                    let needs_pix_fmt = matches!(job.mode, JobMode::Proxy | JobMode::Stream)
                         && job.options.hw_accel == "nvenc";
-                    let (duration_result, pix_fmt) = tokio::join!(
-                        probe_duration(&input_path_clone),
+                    let (meta_result, pix_fmt) = tokio::join!(
+                        probe::probe_media_metadata(&input_path_clone),
                         async {
                             if needs_pix_fmt {
                                 probe_pix_fmt(&input_path_clone).await
@@ -309,8 +1349,8 @@ pub async fn run_queue(
                             }
                         },
                     );
-                    total_duration_us = match duration_result {
-                        Ok(d) if d > 0 => d,
+                    let probed_meta = match meta_result {
+                        Ok(meta) if meta.duration_us > 0 => meta,
                         Ok(_) | Err(_) => {
                             let _ = response_tx
                                 .send(Response::JobError {
@@ -321,7 +1361,37 @@ pub async fn run_queue(
                             continue;
                         }
                     };
-                    nvenc_full_gpu = nvenc_full_gpu_supported(&pix_fmt);
+                    total_duration_us = probed_meta.duration_us;
+                    color_meta = resolve_color_metadata(&probed_meta, &job.options);
+                    nvenc_full_gpu = if needs_pix_fmt {
+                        let codec_for_probe = match job.options.proxy_codec.as_str() {
+                            "h265" => "hevc_nvenc",
+                            _ => "h264_nvenc",
+                        };
+                        hw_caps.supports_full_gpu(&pix_fmt, codec_for_probe).await
+                    } else {
+                        false
+                    };
+                }
+
+                // Preflight: angeforderte proxy_codec x hw_accel Kombination gegen die
+                // Toolchain-Capabilities pruefen (siehe `hwcaps::HwCapabilities::resolve_hw_accel`).
+                // Proxy/Stream sind die einzigen Modi, die `push_proxy_codec_args` nutzen –
+                // BRAW/R3D/Concat/ReWrap haben eigene bzw. keine Codec-Wahl.
+                if matches!(job.mode, JobMode::Proxy | JobMode::Stream) {
+                    let (effective_hw_accel, fallback_message) = hw_caps
+                        .resolve_hw_accel(&job.options.proxy_codec, &job.options.hw_accel)
+                        .await;
+                    if let Some(message) = fallback_message {
+                        eprintln!("Job {job_id}: {message}");
+                        job.options.hw_accel = effective_hw_accel;
+                        let _ = response_tx
+                            .send(Response::JobHwFallback {
+                                id: job_id.clone(),
+                                message,
+                            })
+                            .await;
+                    }
                 }
 
                 // Ausgabedatei bereits vorhanden und Skip aktiviert?
@@ -340,8 +1410,94 @@ pub async fn run_queue(
                 job.attach_to_parent_token(&shutdown_token);
                 let cancel_token = job.cancel_token.clone();
 
-                // FFmpeg-Args nur fuer normale (nicht-Bridge) Jobs aufbauen
-                let args = if is_braw || is_r3d {
+                // Multi-Clip-Concat mit Transitions: eigener Filtergraph, kein build_ffmpeg_args
+                let is_concat = matches!(job.mode, JobMode::Concat);
+                // Fragmentiertes HLS/fMP4-Streaming: eigener Muxer-Aufbau (siehe
+                // `ffmpeg::segmented`), ebenfalls kein build_ffmpeg_args.
+                let is_stream = matches!(job.mode, JobMode::Stream);
+
+                // Target-Quality (`JobOptions::target_vmaf`): fuer normale Proxy-Jobs mit
+                // CRF- ODER QP-basiertem Encoder (h264/h265/av1, SW oder VAAPI/NVENC; kein
+                // ProRes, dessen festes Profil keine Ratenkontrolle kennt). Konvergiert vor dem
+                // eigentlichen Encode auf den CRF/QP-Wert, der den Ziel-VMAF-Score trifft (siehe
+                // `vmaf::converge_crf`s `hw_accel`-Parameter). Ausgenommen: Chunked-Encoding
+                // (`JobOptions::chunked_encode`) – `run_chunked_job`/`build_chunk_args` kennen
+                // keinen `crf_override` und wuerden ihn stillschweigend verwerfen, die teure
+                // Probe-Konvergenz liefe dann umsonst (siehe `JobTargetVmafIgnored` unten).
+                let wants_target_vmaf = !is_braw
+                    && !is_r3d
+                    && !is_concat
+                    && !is_live
+                    && matches!(job.mode, JobMode::Proxy)
+                    && !is_prores(&job.options.proxy_codec)
+                    && job.options.target_vmaf.is_some()
+                    && !job.options.chunked_encode;
+
+                if job.options.chunked_encode
+                    && job.options.target_vmaf.is_some()
+                    && !is_braw
+                    && !is_r3d
+                    && !is_concat
+                    && !is_live
+                    && matches!(job.mode, JobMode::Proxy)
+                {
+                    let _ = response_tx
+                        .send(Response::JobTargetVmafIgnored {
+                            id: job_id.clone(),
+                            message: "target_vmaf wird bei chunked_encode nicht unterstuetzt und wurde ignoriert; Job laeuft mit fester CRF/Ratenkontrolle".to_string(),
+                        })
+                        .await;
+                }
+
+                let crf_override = if wants_target_vmaf {
+                    let target = job.options.target_vmaf.unwrap();
+                    let resolution = job
+                        .options
+                        .proxy_resolution
+                        .as_deref()
+                        .map(|r| r.replace('x', ":"));
+                    match vmaf::converge_crf(
+                        &job_id,
+                        &input_path_clone,
+                        total_duration_us,
+                        &job.options.proxy_codec,
+                        &job.options.hw_accel,
+                        resolution.as_deref(),
+                        target,
+                        job.options.vmaf_crf_min,
+                        job.options.vmaf_crf_max,
+                        job.options.vmaf_tolerance,
+                        job.options.vmaf_max_probes,
+                    )
+                    .await
+                    {
+                        Ok(crf) => Some(crf),
+                        Err(e) => {
+                            eprintln!("VMAF-Konvergenz fuer Job {job_id} fehlgeschlagen, falle auf feste CRF zurueck: {e}");
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                // Klassischer Zwei-Pass-Encode (`RateControl::TwoPass`): nur fuer normale
+                // Proxy-Jobs mit CRF-basiertem SW-Encoder (wie `wants_target_vmaf`) – NVENC/
+                // VAAPI behandeln `TwoPass` als Single-Pass-VBR (siehe `push_nvenc`/`push_vaapi`),
+                // fuer sie lohnt sich der Doppellauf nicht.
+                let is_two_pass = !is_braw
+                    && !is_r3d
+                    && !is_concat
+                    && !is_stream
+                    && matches!(job.mode, JobMode::Proxy)
+                    && job.options.hw_accel == "none"
+                    && !is_prores(&job.options.proxy_codec)
+                    && matches!(job.options.rate_control, Some(RateControl::TwoPass { .. }));
+
+                // FFmpeg-Args nur fuer normale (nicht-Bridge, nicht-Concat, nicht-Stream,
+                // nicht-Zwei-Pass) Jobs aufbauen – `run_two_pass_ffmpeg` baut sich seine
+                // Pass-1/Pass-2-Argumente selbst ueber `build_ffmpeg_args`.
+                let args = if is_braw || is_r3d || is_concat || is_stream || is_two_pass {
                     Vec::new() // wird nicht benutzt
                 } else {
                     build_ffmpeg_args(
@@ -350,13 +1506,30 @@ pub async fn run_queue(
                         &job.mode,
                         &job.options,
                         nvenc_full_gpu,
+                        crf_override,
+                        &color_meta,
                     )
                 };
 
                 let job_input_path = job.input_path.clone();
+                let job_output_dir = job.output_dir.clone();
                 let job_options = job.options.clone();
+                let job_mode = job.mode.clone();
+                let job_attempt = job.attempt;
+                let priority = job.priority;
+
+                // Chunked-Encoding nur fuer normale Proxy-Jobs mit bekannter Gesamtdauer
+                // (keine Bridge-Pipelines, keine Live-Quelle ohne Ende).
+                let is_chunked = !is_braw
+                    && !is_r3d
+                    && !is_live
+                    && job_options.chunked_encode
+                    && matches!(job_mode, JobMode::Proxy);
 
                 job.status = JobState::Queued;
+                if let Err(e) = store.put(&job) {
+                    eprintln!("Job-Store: konnte Job {job_id} nicht schreiben: {e}");
+                }
                 {
                     let mut map = jobs.write().await;
                     map.insert(job_id.clone(), job);
@@ -367,269 +1540,363 @@ pub async fn run_queue(
                     .send(Response::JobQueued { id: job_id.clone() })
                     .await;
 
-                let limit_ref = limit.clone();
-                let running_ref = running.clone();
-                let slot_free_ref = slot_free.clone();
-                let is_paused_ref = is_paused.clone();
-                let pid_slot = Arc::new(AtomicU32::new(0));
+                let pid_set = PidSet::new();
                 {
-                    ffmpeg_pids.write().await.insert(job_id.clone(), pid_slot.clone());
+                    ffmpeg_pids.write().await.insert(job_id.clone(), pid_set.clone());
                 }
-                let ffmpeg_pids_ref = ffmpeg_pids.clone();
-                let resp_tx = response_tx.clone();
-                let jobs_ref = jobs.clone();
-                let job_id_for_monitor = job_id.clone();
-
-                let handle = tokio::spawn(async move {
-                    // Warten bis ein Slot frei ist UND nicht pausiert – oder Job wird gecancelt
-                    loop {
-                        if is_paused_ref.load(Ordering::Acquire) {
-                            tokio::select! {
-                                _ = slot_free_ref.notified() => continue,
-                                _ = cancel_token.cancelled() => {
-                                    ffmpeg_pids_ref.write().await.remove(&job_id);
-                                    jobs_ref.write().await.remove(&job_id);
-                                    let _ = resp_tx.send(Response::JobCancelled { id: job_id.clone() }).await;
-                                    return;
-                                }
-                            }
-                        }
-                        let cur = running_ref.load(Ordering::Acquire);
-                        let lim = limit_ref.load(Ordering::Acquire);
-                        if cur < lim {
-                            if running_ref
-                                .compare_exchange(cur, cur + 1, Ordering::AcqRel, Ordering::Acquire)
-                                .is_ok()
-                            {
-                                break;
-                            }
-                        } else {
-                            tokio::select! {
-                                _ = slot_free_ref.notified() => {}
-                                _ = cancel_token.cancelled() => {
-                                    ffmpeg_pids_ref.write().await.remove(&job_id);
-                                    jobs_ref.write().await.remove(&job_id);
-                                    let _ = resp_tx.send(Response::JobCancelled { id: job_id.clone() }).await;
-                                    return;
-                                }
-                            }
-                        }
-                    }
 
-                    // Status auf Running setzen
-                    {
-                        let mut map = jobs_ref.write().await;
-                        if let Some(j) = map.get_mut(&job_id) {
-                            j.status = JobState::Running;
-                        }
-                    }
+                pending.insert(
+                    job_id.clone(),
+                    PreparedJob {
+                        job_id: job_id.clone(),
+                        job_input_path,
+                        job_output_dir,
+                        job_options,
+                        job_mode,
+                        job_attempt,
+                        is_braw,
+                        is_r3d,
+                        is_chunked,
+                        is_concat,
+                        is_stream,
+                        is_two_pass,
+                        braw_meta,
+                        r3d_meta,
+                        total_duration_us,
+                        args,
+                        output_path,
+                        nvenc_full_gpu,
+                        crf_override,
+                        color_meta,
+                        cancel_token,
+                        pid_set,
+                    },
+                );
+                let seq = next_seq;
+                next_seq += 1;
+                let lane = job_lane(&pending[&job_id].job_mode, &pending[&job_id].job_options);
+                let entry = ReadyEntry { priority, seq, job_id };
+                match lane {
+                    Lane::Cpu => ready_heap_cpu.push(entry),
+                    Lane::Gpu => ready_heap_gpu.push(entry),
+                }
 
-                    // Event-Channel fuer diesen Job-Lauf
-                    let (event_tx, mut event_rx) = mpsc::channel::<FfmpegEvent>(64);
-
-                    // Job in eigenem Task starten (BRAW, R3D oder FFmpeg)
-                    let task_id = job_id.clone();
-                    let task_handle = if is_braw {
-                        let meta = braw_meta.unwrap(); // sicher: is_braw → braw_meta = Some
-                        tokio::spawn(async move {
-                            braw_runner::run_braw_job(
-                                task_id,
-                                job_input_path,
-                                output_path,
-                                &job_options,
-                                meta,
-                                event_tx,
-                                cancel_token,
-                                pid_slot,
-                            )
-                            .await
-                        })
-                    } else if is_r3d {
-                        let meta = r3d_meta.unwrap(); // sicher: is_r3d → r3d_meta = Some
-                        tokio::spawn(async move {
-                            r3d_runner::run_r3d_job(
-                                task_id,
-                                job_input_path,
-                                output_path,
-                                &job_options,
-                                meta,
-                                event_tx,
-                                cancel_token,
-                                pid_slot,
-                            )
-                            .await
-                        })
-                    } else {
-                        tokio::spawn(async move {
-                            runner::run_ffmpeg(
-                                task_id,
-                                args,
-                                &output_path,
-                                total_duration_us,
-                                event_tx,
-                                cancel_token,
-                                pid_slot,
-                            )
-                            .await
-                        })
+                dispatch_ready(
+                    &mut ready_heap_cpu,
+                    &mut ready_heap_gpu,
+                    &mut pending,
+                    cpu_capacity,
+                    &mut cpu_in_use,
+                    gpu_capacity,
+                    &mut gpu_in_use,
+                    &mut running_count,
+                    is_paused,
+                    &jobs,
+                    &store,
+                    &response_tx,
+                    &ffmpeg_pids,
+                    &retry_tx,
+                )
+                .await;
+            }
+            JobCommand::SetMaxParallelCpu(n) => {
+                cpu_capacity = n.max(1);
+                dispatch_ready(
+                    &mut ready_heap_cpu,
+                    &mut ready_heap_gpu,
+                    &mut pending,
+                    cpu_capacity,
+                    &mut cpu_in_use,
+                    gpu_capacity,
+                    &mut gpu_in_use,
+                    &mut running_count,
+                    is_paused,
+                    &jobs,
+                    &store,
+                    &response_tx,
+                    &ffmpeg_pids,
+                    &retry_tx,
+                )
+                .await;
+            }
+            JobCommand::SetMaxParallelGpu(n) => {
+                gpu_capacity = n.max(1);
+                dispatch_ready(
+                    &mut ready_heap_cpu,
+                    &mut ready_heap_gpu,
+                    &mut pending,
+                    cpu_capacity,
+                    &mut cpu_in_use,
+                    gpu_capacity,
+                    &mut gpu_in_use,
+                    &mut running_count,
+                    is_paused,
+                    &jobs,
+                    &store,
+                    &response_tx,
+                    &ffmpeg_pids,
+                    &retry_tx,
+                )
+                .await;
+            }
+            JobCommand::SetPriority(id, new_priority) => {
+                // BinaryHeap unterstuetzt kein Decrease/Increase-Key: Eintraege
+                // fuer `id` verwerfen und mit neuer Prioritaet frisch einreihen.
+                // Wirkt sich nur auf die Dispatch-Reihenfolge aus solange der
+                // Job noch in `pending` (also wirklich `Queued`) ist.
+                let still_pending = pending.contains_key(&id);
+                if still_pending {
+                    // Der Job kann nur in genau einer Lane warten (siehe `job_lane`).
+                    let lane = job_lane(&pending[&id].job_mode, &pending[&id].job_options);
+                    let heap = match lane {
+                        Lane::Cpu => &mut ready_heap_cpu,
+                        Lane::Gpu => &mut ready_heap_gpu,
                     };
-
-                    // Events weiterleiten an IPC
-                    while let Some(event) = event_rx.recv().await {
-                        match event {
-                            FfmpegEvent::Progress {
-                                id,
-                                percent,
-                                fps,
-                                speed,
-                                frame,
-                            } => {
-                                {
-                                    let mut map = jobs_ref.write().await;
-                                    if let Some(j) = map.get_mut(&id) {
-                                        j.percent = percent;
-                                    }
-                                }
-                                let _ = resp_tx
-                                    .send(Response::JobProgress {
-                                        id,
-                                        percent,
-                                        fps,
-                                        speed,
-                                        frame,
-                                    })
-                                    .await;
-                            }
-                            FfmpegEvent::Done { id } => {
-                                {
-                                    let mut map = jobs_ref.write().await;
-                                    if let Some(j) = map.get_mut(&id) {
-                                        j.status = JobState::Done;
-                                        j.percent = 100.0;
-                                    }
-                                }
-                                let _ = resp_tx.send(Response::JobDone { id }).await;
-                            }
-                            FfmpegEvent::Error { id, message } => {
-                                {
-                                    let mut map = jobs_ref.write().await;
-                                    if let Some(j) = map.get_mut(&id) {
-                                        j.status = JobState::Error;
-                                    }
-                                }
-                                let _ = resp_tx
-                                    .send(Response::JobError { id, message })
-                                    .await;
-                            }
-                            FfmpegEvent::Cancelled { id } => {
-                                {
-                                    let mut map = jobs_ref.write().await;
-                                    if let Some(j) = map.get_mut(&id) {
-                                        j.status = JobState::Cancelled;
-                                    }
-                                }
-                                let _ = resp_tx
-                                    .send(Response::JobCancelled { id })
-                                    .await;
-                            }
-                        }
-                    }
-
-                    let task_label = if is_braw { "braw-bridge" } else if is_r3d { "r3d-bridge" } else { "FFmpeg" };
-                    match task_handle.await {
-                        Ok(Ok(())) => {}  // Normale Beendigung: terminales Event wurde bereits gesendet
-                        Ok(Err(e)) => {
-                            let _ = resp_tx.send(Response::JobError {
-                                id: job_id.clone(),
-                                message: format!("{task_label} konnte nicht ausgefuehrt werden: {e}"),
-                            }).await;
-                        }
-                        Err(e) => {
-                            let _ = resp_tx.send(Response::JobError {
-                                id: job_id.clone(),
-                                message: format!("{task_label}-Task Panik: {e}"),
-                            }).await;
-                        }
-                    }
-
-                    // Slot freigeben und wartende Jobs benachrichtigen
-                    running_ref.fetch_sub(1, Ordering::AcqRel);
-                    slot_free_ref.notify_waiters();
-
-                    // PID-Eintrag und Job aus HashMaps entfernen
-                    ffmpeg_pids_ref.write().await.remove(&job_id);
-                    jobs_ref.write().await.remove(&job_id);
-                });
-
-                // Monitor: wenn der Job-Task panikt → JobError an Python senden
-                let monitor_tx = response_tx.clone();
-                let monitor_id = job_id_for_monitor;
-                tokio::spawn(async move {
-                    if let Err(e) = handle.await {
-                        let _ = monitor_tx.send(Response::JobError {
-                            id: monitor_id,
-                            message: format!("Job-Task-Panik: {e}"),
-                        }).await;
+                    let remaining: Vec<ReadyEntry> =
+                        heap.drain().filter(|e| e.job_id != id).collect();
+                    *heap = remaining.into_iter().collect();
+                    let seq = next_seq;
+                    next_seq += 1;
+                    heap.push(ReadyEntry { priority: new_priority, seq, job_id: id.clone() });
+                }
+                {
+                    let mut map = jobs.write().await;
+                    if let Some(j) = map.get_mut(&id) {
+                        j.priority = new_priority;
                     }
+                }
+                if still_pending {
+                    dispatch_ready(
+                        &mut ready_heap_cpu,
+                        &mut ready_heap_gpu,
+                        &mut pending,
+                        cpu_capacity,
+                        &mut cpu_in_use,
+                        gpu_capacity,
+                        &mut gpu_in_use,
+                        &mut running_count,
+                        is_paused,
+                        &jobs,
+                        &store,
+                        &response_tx,
+                        &ffmpeg_pids,
+                        &retry_tx,
+                    )
+                    .await;
+                }
+            }
+            JobCommand::Drain => {
+                is_draining = true;
+                // Noch nicht dispatchte Jobs sofort verwerfen statt sie zu
+                // starten; bereits laufende Jobs bleiben unberuehrt und duerfen
+                // natuerlich zu Ende laufen.
+                let pending_ids: Vec<String> = pending.keys().cloned().collect();
+                for id in pending_ids {
+                    pending.remove(&id);
+                    ffmpeg_pids.write().await.remove(&id);
+                    jobs.write().await.remove(&id);
+                    let _ = store.remove(&id);
+                    let _ = response_tx.send(Response::JobCancelled { id }).await;
+                }
+                ready_heap_cpu.clear();
+                ready_heap_gpu.clear();
+                let _ = drain_tx.send(if running_count == 0 {
+                    DrainStatus::Complete
+                } else {
+                    DrainStatus::Draining { remaining: running_count }
                 });
             }
-            JobCommand::SetMaxParallel(n) => {
-                limit.store(n.max(1), Ordering::Release);
-                slot_free.notify_waiters();
+            JobCommand::JobFinished(lane, cost) => {
+                match lane {
+                    Lane::Cpu => cpu_in_use = cpu_in_use.saturating_sub(cost),
+                    Lane::Gpu => gpu_in_use = gpu_in_use.saturating_sub(cost),
+                }
+                running_count = running_count.saturating_sub(1);
+                if is_draining {
+                    let _ = drain_tx.send(if running_count == 0 {
+                        DrainStatus::Complete
+                    } else {
+                        DrainStatus::Draining { remaining: running_count }
+                    });
+                }
+                dispatch_ready(
+                    &mut ready_heap_cpu,
+                    &mut ready_heap_gpu,
+                    &mut pending,
+                    cpu_capacity,
+                    &mut cpu_in_use,
+                    gpu_capacity,
+                    &mut gpu_in_use,
+                    &mut running_count,
+                    is_paused,
+                    &jobs,
+                    &store,
+                    &response_tx,
+                    &ffmpeg_pids,
+                    &retry_tx,
+                )
+                .await;
             }
             JobCommand::PauseAll => {
-                is_paused.store(true, Ordering::Release);
+                is_paused = true;
                 let pids = ffmpeg_pids.read().await;
-                for pid_slot in pids.values() {
-                    let pid = pid_slot.load(Ordering::Acquire);
-                    if pid != 0 {
-                        unsafe { libc::kill(pid as libc::pid_t, libc::SIGSTOP); }
-                    }
+                for pid_set in pids.values() {
+                    pid_set.signal_all(libc::SIGSTOP).await;
+                    pid_set.set_paused(true);
                 }
             }
             JobCommand::ResumeAll => {
-                is_paused.store(false, Ordering::Release);
+                is_paused = false;
                 let pids = ffmpeg_pids.read().await;
-                for pid_slot in pids.values() {
-                    let pid = pid_slot.load(Ordering::Acquire);
-                    if pid != 0 {
-                        unsafe { libc::kill(pid as libc::pid_t, libc::SIGCONT); }
-                    }
+                for pid_set in pids.values() {
+                    pid_set.signal_all(libc::SIGCONT).await;
+                    pid_set.set_paused(false);
                 }
                 drop(pids);
-                slot_free.notify_waiters();
+                dispatch_ready(
+                    &mut ready_heap_cpu,
+                    &mut ready_heap_gpu,
+                    &mut pending,
+                    cpu_capacity,
+                    &mut cpu_in_use,
+                    gpu_capacity,
+                    &mut gpu_in_use,
+                    &mut running_count,
+                    is_paused,
+                    &jobs,
+                    &store,
+                    &response_tx,
+                    &ffmpeg_pids,
+                    &retry_tx,
+                )
+                .await;
             }
-            JobCommand::Cancel(id) => {
-                // Falls der FFmpeg-Prozess via SIGSTOP pausiert ist, zuerst
-                // SIGCONT senden – sonst kann er 'q' nicht verarbeiten und
-                // child.wait() blockiert endlos.
-                {
-                    let pids = ffmpeg_pids.read().await;
-                    if let Some(pid_slot) = pids.get(&id) {
-                        let pid = pid_slot.load(Ordering::Acquire);
-                        if pid != 0 {
-                            unsafe { libc::kill(pid as libc::pid_t, libc::SIGCONT); }
+            JobCommand::PauseJob(id) => {
+                let mut map = jobs.write().await;
+                if let Some(job) = map.get_mut(&id) {
+                    if matches!(job.status, JobState::Running) {
+                        job.status = JobState::Paused;
+                        drop(map);
+                        let pids = ffmpeg_pids.read().await;
+                        if let Some(pid_set) = pids.get(&id) {
+                            pid_set.signal_all(libc::SIGSTOP).await;
+                            pid_set.set_paused(true);
                         }
+                        drop(pids);
+                        let _ = response_tx.send(Response::JobPaused { id }).await;
                     }
                 }
-                let map = jobs.read().await;
-                if let Some(job) = map.get(&id) {
-                    job.cancel_token.cancel();
+            }
+            JobCommand::ResumeJob(id) => {
+                let mut map = jobs.write().await;
+                if let Some(job) = map.get_mut(&id) {
+                    if matches!(job.status, JobState::Paused) {
+                        job.status = JobState::Running;
+                        drop(map);
+                        let pids = ffmpeg_pids.read().await;
+                        if let Some(pid_set) = pids.get(&id) {
+                            pid_set.signal_all(libc::SIGCONT).await;
+                            pid_set.set_paused(false);
+                        }
+                        drop(pids);
+                        let _ = response_tx.send(Response::JobResumed { id }).await;
+                    }
+                }
+            }
+            JobCommand::Cancel(id) => {
+                if pending.remove(&id).is_some() {
+                    // Noch nicht dispatcht: Tokens wurden nie reserviert, daher
+                    // reicht es den Job aus den Maps zu entfernen. Der passende
+                    // ready_heap-Eintrag bleibt als stale zurueck und wird beim
+                    // naechsten `try_dispatch` uebersprungen (siehe dort).
+                    ffmpeg_pids.write().await.remove(&id);
+                    jobs.write().await.remove(&id);
+                    let _ = store.remove(&id);
+                    let _ = response_tx.send(Response::JobCancelled { id }).await;
+                } else {
+                    // Laeuft bereits: Falls der FFmpeg-Prozess via SIGSTOP
+                    // pausiert ist, zuerst SIGCONT senden – sonst kann er 'q'
+                    // nicht verarbeiten und child.wait() blockiert endlos.
+                    {
+                        let pids = ffmpeg_pids.read().await;
+                        if let Some(pid_set) = pids.get(&id) {
+                            pid_set.signal_all(libc::SIGCONT).await;
+                        }
+                    }
+                    let map = jobs.read().await;
+                    if let Some(job) = map.get(&id) {
+                        job.cancel_token.cancel();
+                    }
                 }
             }
             JobCommand::GetStatus(reply) => {
                 let mut map = jobs.write().await;
                 // Alte abgeschlossene Jobs entfernen
                 map.retain(|_, job| {
-                    matches!(job.status, JobState::Running | JobState::Queued)
+                    matches!(job.status, JobState::Running | JobState::Queued | JobState::Paused)
                 });
-                let statuses: Vec<JobStatus> = map.values().map(|j| j.to_status()).collect();
+                // Reihenfolge je Lane fuer die Positionsangabe (1-basiert, siehe `Lane`).
+                let ordered_in = |heap: &BinaryHeap<ReadyEntry>| -> Vec<String> {
+                    let mut ordered: Vec<&ReadyEntry> = heap
+                        .iter()
+                        .filter(|e| pending.contains_key(&e.job_id))
+                        .collect();
+                    ordered.sort();
+                    ordered.reverse();
+                    ordered.into_iter().map(|e| e.job_id.clone()).collect()
+                };
+                let ordered_cpu = ordered_in(&ready_heap_cpu);
+                let ordered_gpu = ordered_in(&ready_heap_gpu);
+                let statuses: Vec<JobStatus> = map
+                    .values()
+                    .map(|j| {
+                        let lane = job_lane(&j.mode, &j.options);
+                        let queue_position = if matches!(j.status, JobState::Queued) {
+                            let ordered = match lane {
+                                Lane::Cpu => &ordered_cpu,
+                                Lane::Gpu => &ordered_gpu,
+                            };
+                            ordered.iter().position(|id| id == &j.id).map(|p| p + 1)
+                        } else {
+                            None
+                        };
+                        let running_lane =
+                            matches!(j.status, JobState::Running | JobState::Paused).then_some(lane);
+                        j.to_status(queue_position, running_lane)
+                    })
+                    .collect();
                 let _ = reply.send(statuses);
             }
         }
     }
 }
 
+/// Kombiniert vom Nutzer gesetzte Farb-Parameter (`JobOptions::color_transfer`/
+/// `color_primaries`/`color_space`/`tonemap`) mit den von `probe_media_metadata`
+/// ermittelten Werten der Quelle. Nutzer-Werte haben Vorrang, weil Container-Tags
+/// in der Praxis oft falsch gesetzt sind; fehlt ein Nutzer-Wert, wird der geprobte
+/// Wert uebernommen. Mastering-Display/CLL werden immer aus der Quelle uebernommen
+/// (kein manueller Override vorgesehen).
+fn resolve_color_metadata(meta: &probe::MediaMetadata, options: &JobOptions) -> runner::ColorMetadata {
+    runner::ColorMetadata {
+        color_transfer: options
+            .color_transfer
+            .clone()
+            .unwrap_or_else(|| meta.color_transfer.clone()),
+        color_primaries: options
+            .color_primaries
+            .clone()
+            .unwrap_or_else(|| meta.color_primaries.clone()),
+        color_space: options
+            .color_space
+            .clone()
+            .unwrap_or_else(|| meta.color_space.clone()),
+        mastering_display: meta.mastering_display.clone(),
+        max_cll: meta.max_cll.clone(),
+        tonemap: options.tonemap,
+    }
+}
+
 /// Ermittelt das Pixel-Format des ersten Video-Streams via ffprobe.
 /// Gibt einen leeren String zurueck wenn das Format nicht ermittelt werden kann
 /// (fuehrt dann zur sicheren Hybrid-Pipeline).
@@ -656,46 +1923,3 @@ async fn probe_pix_fmt(path: &Path) -> String {
     }
 }
 
-/// Gibt true zurueck wenn NVDEC + scale_cuda das gegebene Pixel-Format unterstuetzen.
-/// NVDEC unterstuetzt 4:2:0-Formate (8-bit und 10-bit); 4:2:2 (z.B. p210le von
-/// Sony FX MXF) und andere exotische Formate erfordern die Hybrid-Pipeline.
-fn nvenc_full_gpu_supported(pix_fmt: &str) -> bool {
-    matches!(
-        pix_fmt,
-        "yuv420p" | "nv12" | "yuvj420p"
-            | "yuv420p10le" | "yuv420p10be"
-            | "p010le" | "p010be" | "p016le"
-            | "yuv420p12le" | "p012le"
-    )
-}
-
-/// Ermittelt die Dauer einer Mediendatei in Mikrosekunden via ffprobe.
-async fn probe_duration(path: &Path) -> Result<i64> {
-    let output = tokio::process::Command::new("ffprobe")
-        .args([
-            "-v",
-            "quiet",
-            "-show_entries",
-            "format=duration",
-            "-of",
-            "default=noprint_wrappers=1:nokey=1",
-        ])
-        .arg(path.as_os_str())
-        .output()
-        .await
-        .map_err(|e| anyhow::anyhow!("ffprobe konnte nicht gestartet werden: {e}"))?;
-
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "ffprobe beendet mit Exit-Code: {}",
-            output.status.code().unwrap_or(-1)
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let seconds: f64 = stdout
-        .trim()
-        .parse()
-        .map_err(|e| anyhow::anyhow!("ffprobe Dauer nicht parsebar '{}': {e}", stdout.trim()))?;
-    Ok((seconds * 1_000_000.0) as i64)
-}
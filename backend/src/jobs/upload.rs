@@ -0,0 +1,98 @@
+// Post-Transcode-Upload-Sink: streamt eine fertig transkodierte Datei zu einem
+// S3-kompatiblen Endpunkt (siehe `JobOptions::upload_destination`). Laeuft als
+// optionaler Abschluss-Schritt nach `FfmpegEvent::Done` in `transcode::run_queue`,
+// bevor der Job als `JobDone` gilt.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use tokio::sync::mpsc;
+
+use crate::ipc::protocol::{Response, UploadDestination};
+
+/// Baut einen S3-Client fuer den gegebenen Endpunkt. Keine Region-Autodiscovery
+/// und kein Credentials-Chaining (EC2-Metadata etc.) – die Zugangsdaten kommen
+/// ausschliesslich aus `UploadDestination`, damit das Backend ohne weiteres
+/// Umgebungssetup auch gegen selbstgehostete MinIO-Endpunkte laeuft.
+fn build_client(dest: &UploadDestination) -> Client {
+    let credentials = Credentials::new(
+        &dest.access_key_id,
+        &dest.secret_access_key,
+        None,
+        None,
+        "proxy-generator",
+    );
+    let config = aws_sdk_s3::Config::builder()
+        .endpoint_url(&dest.endpoint)
+        .region(Region::new(dest.region.clone()))
+        .credentials_provider(credentials)
+        .behavior_version(BehaviorVersion::latest())
+        .build();
+    Client::from_conf(config)
+}
+
+/// Leitet den Objekt-Key aus `key_prefix` und dem Dateinamen von `path` ab.
+fn object_key(dest: &UploadDestination, path: &Path, fallback_id: &str) -> String {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| fallback_id.to_string());
+    if dest.key_prefix.is_empty() {
+        file_name
+    } else {
+        format!("{}/{}", dest.key_prefix.trim_end_matches('/'), file_name)
+    }
+}
+
+/// Laedt `path` zum konfigurierten Bucket hoch und meldet den Fortschritt ueber
+/// `resp_tx` als `Response::JobUploading`. Ein einzelner PUT liefert keinen
+/// echten Zwischenfortschritt, daher nur 0% (Start) und 100% (Abschluss) –
+/// fuer sehr grosse Dateien waere ein Multipart-Upload mit Part-weisem
+/// Fortschritt der naechste Schritt.
+/// Gibt die URL des hochgeladenen Objekts zurueck.
+pub async fn upload_output(
+    id: &str,
+    path: &Path,
+    dest: &UploadDestination,
+    resp_tx: &mpsc::Sender<Response>,
+) -> Result<String> {
+    let _ = resp_tx
+        .send(Response::JobUploading {
+            id: id.to_string(),
+            percent: 0.0,
+        })
+        .await;
+
+    let client = build_client(dest);
+    let key = object_key(dest, path, id);
+
+    let body = ByteStream::from_path(path)
+        .await
+        .context("Datei konnte nicht zum Hochladen geoeffnet werden")?;
+
+    client
+        .put_object()
+        .bucket(&dest.bucket)
+        .key(&key)
+        .body(body)
+        .send()
+        .await
+        .context("S3-Upload fehlgeschlagen")?;
+
+    let _ = resp_tx
+        .send(Response::JobUploading {
+            id: id.to_string(),
+            percent: 100.0,
+        })
+        .await;
+
+    Ok(format!(
+        "{}/{}/{}",
+        dest.endpoint.trim_end_matches('/'),
+        dest.bucket,
+        key
+    ))
+}
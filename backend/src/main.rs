@@ -9,9 +9,11 @@ mod jobs;
 use std::sync::Arc;
 
 use anyhow::Result;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 
+use ffmpeg::hwcaps::HwCapabilities;
 use ipc::protocol::Response;
+use jobs::store::JobStore;
 use jobs::transcode::{self, JobQueue};
 
 #[tokio::main]
@@ -19,43 +21,128 @@ async fn main() -> Result<()> {
     // Channel fuer Responses (von Job-Queue an stdout-Writer)
     let (response_tx, response_rx) = mpsc::channel::<Response>(256);
 
-    // max_parallel aus CLI-Argument lesen (--max-parallel N), Fallback: 1
-    let max_parallel = std::env::args()
-        .skip_while(|a| a != "--max-parallel")
+    // Kapazitaet der CPU-Lane (--max-parallel-cpu N), Fallback: Kernzahl der
+    // Maschine (siehe `jobs::transcode::job_lane` fuer die Lane-Aufteilung).
+    let max_parallel_cpu = std::env::args()
+        .skip_while(|a| a != "--max-parallel-cpu")
         .nth(1)
         .and_then(|s| s.parse::<usize>().ok())
         .filter(|&n| n >= 1)
-        .unwrap_or(1);
-    let (queue, cmd_rx) = JobQueue::new(max_parallel, response_tx.clone());
+        .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+
+    // Kapazitaet der GPU-Lane (--max-parallel-gpu N), Fallback: konservativ 2 –
+    // mehr gleichzeitige NVENC/VAAPI-Sessions als das unterstuetzen die meisten
+    // Consumer-GPUs ohnehin nicht.
+    let max_parallel_gpu = std::env::args()
+        .skip_while(|a| a != "--max-parallel-gpu")
+        .nth(1)
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|&n| n >= 1)
+        .unwrap_or(2);
+
+    // Persistente Job-Ablage (--job-store PFAD), Fallback: Systemtempverzeichnis.
+    // Ueberlebt Absturz/Neustart: JobQueue::new liest sie ein und reiht offene Jobs neu ein.
+    let job_store_path = std::env::args()
+        .skip_while(|a| a != "--job-store")
+        .nth(1)
+        .unwrap_or_else(|| {
+            std::env::temp_dir()
+                .join("proxy-gen-jobs.sled")
+                .to_string_lossy()
+                .to_string()
+        });
+    let job_store = JobStore::open(&job_store_path)?;
+
+    // Adresse fuer den optionalen TCP-Transport (--listen HOST:PORT), zusaetzlich
+    // zu stdin/stdout (siehe `ipc::server::run_tcp_listener`). Ohne diese Option
+    // laeuft der Server wie bisher rein ueber Pipes.
+    let listen_addr = std::env::args().skip_while(|a| a != "--listen").nth(1);
+
+    // Hardware-Faehigkeiten (NVENC-Full-GPU-Pfad) einmalig beim Start abfragen,
+    // statt auf einer statischen Pixelformat-Allowlist zu beharren.
+    let hw_caps = Arc::new(HwCapabilities::probe().await);
+
+    let (queue, cmd_rx) = JobQueue::new(max_parallel_cpu, response_tx.clone(), &job_store);
     let global_shutdown_token = queue.shutdown_token();
+    let retry_cmd_tx = queue.cmd_sender();
+    let drain_tx = queue.drain_sender();
     let queue = Arc::new(queue);
 
-    // Shutdown-Channel
-    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    // Broadcast-Hub: alle Responses (Queue-Events wie Ad-hoc-Fehler aus
+    // `ipc::server::handle_request`) laufen weiterhin ueber den einen
+    // `response_tx`/`response_rx`-Channel, werden aber von diesem Forwarder-Task
+    // an alle Transporte (stdout, beliebig viele TCP-Clients) verteilt, statt nur
+    // an einen einzigen Empfaenger zu gehen.
+    let (response_broadcast_tx, _) = broadcast::channel::<Response>(256);
+    let forwarder_broadcast_tx = response_broadcast_tx.clone();
+    tokio::spawn(async move {
+        let mut response_rx = response_rx;
+        while let Some(response) = response_rx.recv().await {
+            let _ = forwarder_broadcast_tx.send(response);
+        }
+    });
 
     // stdout-Writer Task: Schreibt Response-Events als NDJSON
-    let stdout_handle = tokio::spawn(ipc::server::write_stdout(response_rx));
+    let stdout_handle = tokio::spawn(ipc::server::write_stdout(response_broadcast_tx.subscribe()));
 
     // Job-Queue Runner Task: Verarbeitet Job-Kommandos
     let queue_resp_tx = response_tx.clone();
     let queue_handle = tokio::spawn(transcode::run_queue(
         cmd_rx,
-        max_parallel,
+        max_parallel_cpu,
+        max_parallel_gpu,
         queue_resp_tx,
         global_shutdown_token.clone(),
+        job_store,
+        retry_cmd_tx,
+        drain_tx,
+        hw_caps,
     ));
 
+    // Drain-Watcher Task: meldet Fortschritt eines laufenden Drains an Python
+    let mut drain_status_rx = queue.drain_status();
+    let drain_resp_tx = response_tx.clone();
+    tokio::spawn(async move {
+        while drain_status_rx.changed().await.is_ok() {
+            let remaining = match *drain_status_rx.borrow() {
+                transcode::DrainStatus::Draining { remaining } => Some(remaining),
+                transcode::DrainStatus::Complete => Some(0),
+                transcode::DrainStatus::Idle => None,
+            };
+            if let Some(remaining) = remaining {
+                let _ = drain_resp_tx.send(Response::DrainStatus { remaining }).await;
+            }
+        }
+    });
+
     // stdin-Reader Task: Liest Requests und dispatcht sie
     let stdin_resp_tx = response_tx.clone();
-    let stdin_handle = tokio::spawn(ipc::server::read_stdin(queue.clone(), stdin_resp_tx, shutdown_tx));
+    let stdin_handle = tokio::spawn(ipc::server::read_stdin(queue.clone(), stdin_resp_tx, global_shutdown_token.clone()));
     let stdin_abort = stdin_handle.abort_handle();
 
     // stdout-Writer AbortHandle fuer spaetere Bereinigung
     let stdout_abort = stdout_handle.abort_handle();
 
-    // Auf Shutdown warten (entweder via Shutdown-Request, stdin EOF, oder stdout-Fehler)
+    // TCP-Listener Task (optional, nur mit --listen): gleiches Protokoll wie
+    // stdin/stdout, fuer Clients ohne eigenen Kindprozess-Pipe-Zugriff.
+    if let Some(addr) = listen_addr {
+        let tcp_queue = queue.clone();
+        let tcp_resp_tx = response_tx.clone();
+        let tcp_broadcast_tx = response_broadcast_tx.clone();
+        let tcp_shutdown_token = global_shutdown_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                ipc::server::run_tcp_listener(addr, tcp_queue, tcp_resp_tx, tcp_broadcast_tx, tcp_shutdown_token).await
+            {
+                eprintln!("TCP-Listener Fehler: {e}");
+            }
+        });
+    }
+
+    // Auf Shutdown warten (entweder via Shutdown-Request ueber irgendeinen
+    // Transport, stdin EOF, oder stdout-Fehler)
     tokio::select! {
-        _ = shutdown_rx => {
+        () = global_shutdown_token.cancelled() => {
             eprintln!("Shutdown-Signal empfangen, beende...");
         }
         result = stdin_handle => {
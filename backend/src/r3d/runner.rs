@@ -200,12 +200,17 @@ fn build_r3d_ffmpeg_args(
         .proxy_resolution
         .as_deref()
         .map(|r| r.replace('x', ":"));
+    // HDR-Erkennung laeuft nicht ueber die R3D-Bridge – Default-Farbmetadaten
+    // (kein Tonemap, keine Tags) bis die Bridge eigene Farbraum-Infos liefert.
     push_proxy_codec_args(
         &mut args,
         &options.proxy_codec,
         &options.hw_accel,
         resolution.as_deref(),
         false, // full_gpu=false: kein NVDEC moeglich, CPU-Decode → GPU-Encode
+        None,
+        None, // Rate-Control-Modi werden ueber die R3D-Bridge nicht unterstuetzt
+        &crate::ffmpeg::runner::ColorMetadata::default(),
     );
 
     // Audio-Codec (PCM, nur wenn Audio vorhanden)
@@ -242,6 +247,7 @@ pub async fn run_r3d_job(
     tx: mpsc::Sender<FfmpegEvent>,
     cancel: CancellationToken,
     pid_slot: Arc<AtomicU32>,
+    ffmpeg_pid_slot: Arc<AtomicU32>,
 ) -> Result<()> {
     let bridge = find_r3d_bridge();
 
@@ -303,6 +309,10 @@ pub async fn run_r3d_job(
         .spawn()
         .context("FFmpeg konnte nicht gestartet werden")?;
 
+    // PID von FFmpeg speichern (fuer Pause/Resume SIGSTOP/SIGCONT) – r3d-bridge
+    // und FFmpeg sind zwei getrennte Prozesse, beide muessen signalisiert werden.
+    ffmpeg_pid_slot.store(ffmpeg_child.id().unwrap_or(0), Ordering::Release);
+
     let total_frames = meta.frame_count;
 
     // Event-Loop: r3d-bridge stderr lesen fuer Progress, Cancel abfangen
@@ -317,6 +327,7 @@ pub async fn run_r3d_job(
                 let _ = bridge_child.wait().await;
                 let _ = ffmpeg_child.wait().await;
                 pid_slot.store(0, Ordering::Release);
+                ffmpeg_pid_slot.store(0, Ordering::Release);
                 cleanup_audio(&audio_wav);
                 let _ = tx
                     .send(FfmpegEvent::Cancelled {
@@ -344,6 +355,7 @@ pub async fn run_r3d_job(
                                         fps: 0.0,
                                         speed: 0.0,
                                         frame,
+                                        elapsed_us: 0, // r3d-bridge trackt Frames, keine out_time_us
                                     })
                                     .await;
                             }
@@ -354,6 +366,7 @@ pub async fn run_r3d_job(
                         let bridge_status = bridge_child.wait().await?;
                         let ffmpeg_status = ffmpeg_child.wait().await?;
                         pid_slot.store(0, Ordering::Release);
+                        ffmpeg_pid_slot.store(0, Ordering::Release);
                         cleanup_audio(&audio_wav);
 
                         if !bridge_status.success() {
@@ -389,6 +402,7 @@ pub async fn run_r3d_job(
                         let _ = ffmpeg_child.kill().await;
                         let _ = ffmpeg_child.wait().await;
                         pid_slot.store(0, Ordering::Release);
+                        ffmpeg_pid_slot.store(0, Ordering::Release);
                         cleanup_audio(&audio_wav);
                         let _ = tx
                             .send(FfmpegEvent::Error {